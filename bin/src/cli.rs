@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 
 /// Struct to describe the arguments for CLAP
 #[derive(Parser)]
@@ -33,6 +33,10 @@ pub(super) enum Commands {
         #[arg(short = 'c', long, default_value_t = 1_000_000)]
         star_count: usize,
 
+        /// Percentage of stars that are generated as binary star systems
+        #[arg(short, long, default_value_t = 0)]
+        binary_percentage: u8,
+
         /// Seed for the random number generator, leave blank for a random seed, supports
         #[arg(short, long)]
         seed: Option<String>,
@@ -74,9 +78,76 @@ pub(super) enum Commands {
         #[arg(short, long, default_value = "program.json")]
         program: String,
 
+        /// Replace the orbits of bodies with an N-body simulation, integrating every given number
+        /// of hours. Bodies need a mass to attract other bodies
+        #[arg(short, long)]
+        n_body_step: Option<f64>,
+
+        /// Show bodies where they were when their light left them, rather than where they are at
+        /// the time of the observation
+        #[arg(short, long)]
+        light_time: bool,
+
+        /// Also log eclipses seen from each observatory in the given format
+        #[arg(long, value_enum)]
+        eclipses: Option<EclipseFormat>,
+
         /// Output directory for observations, output structure will be like
         /// `/output_path/observatory_id/time.ext`
         #[arg(short, long, default_value = ".")]
         output: PathBuf,
     },
+    /// Print when a body rises, sets, and transits for each observatory, as CSV with the columns
+    /// `observatory,body,event,time,altitude,azimuth` (times in hours, angles in radians)
+    Almanac {
+        /// Name of the body, or its ID with the indices separated by dashes e.g. `2-0`
+        body: String,
+
+        /// Time to start looking for events in hours since epoch
+        #[arg(short, long, default_value_t = 0.0)]
+        start_time: f64,
+
+        /// Time to stop looking for events in hours since epoch
+        #[arg(short, long)]
+        end_time: f64,
+
+        /// Time between samples of the sky (in hours), should be well under half a day of the
+        /// observatories' bodies
+        #[arg(short = 't', long, default_value_t = 1.0)]
+        step_size: f64,
+
+        /// Only use the observatory with this name
+        #[arg(short = 'n', long)]
+        observatory: Option<String>,
+
+        /// Use where bodies were when their light left them, rather than where they are at the
+        /// time of each sample
+        #[arg(short, long)]
+        light_time: bool,
+
+        /// Path to a JSON file that represents the bodies in the universe, if present with
+        /// [`Self::Almanac::observatories`] this takes precedence over [`Self::Almanac::program`]
+        #[arg(short, long)]
+        universe: Option<PathBuf>,
+
+        /// Path to a JSON file that represents the observatories, if present with
+        /// [`Self::Almanac::universe`] this takes precedence over [`Self::Almanac::program`]
+        #[arg(short = 'b', long)]
+        observatories: Option<PathBuf>,
+
+        /// Path that contains a json representation of the program settings
+        #[arg(short, long, default_value = "program.json")]
+        program: String,
+    },
+}
+
+/// Formats that eclipses can be logged in
+#[derive(Clone, Copy, ValueEnum)]
+pub(super) enum EclipseFormat {
+    /// Sentences in a text file for each time step
+    Text,
+    /// One JSON object per line in a file for each observatory
+    Jsonl,
+    /// Comma separated values in a file for each observatory
+    Csv,
 }
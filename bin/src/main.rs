@@ -9,8 +9,13 @@ use std::{
 
 use astrograph::{
     body::{observatory::WeakObservatory, Body},
+    csv,
+    dynamic::n_body::{Integrator, NBody},
     generator::{artifexian::ArtifexianBuilder, Generator},
-    output::svg::Svg,
+    output::{
+        logger::eclipse::{record::LogFormat, Logger},
+        svg::Svg,
+    },
     program::{Program, ProgramBuilder},
     projection::StatelessOrthographic,
 };
@@ -36,12 +41,14 @@ fn try_main() -> Result<(), Box<dyn Error>> {
     match args.sub_command {
         cli::Commands::Build {
             star_count,
+            binary_percentage,
             seed,
             observatory_output,
             universe_output,
         } => build(
             seed.as_ref(),
             star_count,
+            binary_percentage,
             &universe_output,
             &observatory_output,
         ),
@@ -52,6 +59,9 @@ fn try_main() -> Result<(), Box<dyn Error>> {
             universe,
             observatories,
             program,
+            n_body_step,
+            light_time,
+            eclipses,
             output,
         } => simulate(
             start_time,
@@ -60,8 +70,32 @@ fn try_main() -> Result<(), Box<dyn Error>> {
             universe.as_ref(),
             observatories.as_ref(),
             &program,
+            n_body_step,
+            light_time,
+            eclipses,
             &output,
         ),
+        cli::Commands::Almanac {
+            body,
+            start_time,
+            end_time,
+            step_size,
+            observatory,
+            light_time,
+            universe,
+            observatories,
+            program,
+        } => almanac(
+            &body,
+            start_time,
+            end_time,
+            step_size,
+            observatory.as_deref(),
+            light_time,
+            universe.as_ref(),
+            observatories.as_ref(),
+            &program,
+        ),
     }
 }
 
@@ -88,6 +122,7 @@ fn setup_log(quiet: u8, verbosity: u8) {
 fn build(
     seed: Option<&String>,
     star_count: usize,
+    binary_percentage: u8,
     universe_output: &Path,
     observatory_output: &Path,
 ) -> Result<(), Box<dyn Error>> {
@@ -118,6 +153,7 @@ fn build(
     let mut rng = XorShiftRng::from_seed(seed_num);
     let (tree, observatories) = ArtifexianBuilder::default()
         .star_count(star_count)
+        .binary_star_percentage(binary_percentage)
         .build()
         .unwrap()
         .generate(&mut rng);
@@ -159,22 +195,150 @@ fn simulate(
     universe: Option<&PathBuf>,
     observatories: Option<&PathBuf>,
     program: &str,
+    n_body_step: Option<f64>,
+    light_time: bool,
+    eclipses: Option<cli::EclipseFormat>,
     output: &Path,
 ) -> Result<(), Box<dyn Error>> {
     trace!("Entered Simulation function in binary");
+    let mut program = load_program(universe, observatories, program)?;
+    program.add_output(Box::new(Svg::new(StatelessOrthographic())));
+    program.set_output_path(output);
+
+    if let Some(step) = n_body_step {
+        // Float may be f32 or f64 depending on features
+        #[allow(clippy::cast_possible_truncation, clippy::unnecessary_cast)]
+        program.set_n_body(Some(NBody::new(
+            step as astrograph::Float,
+            Integrator::default(),
+        )));
+    }
+
+    if light_time {
+        program.set_light_time(true);
+    }
+
+    if let Some(format) = eclipses {
+        program.add_output(Box::new(Logger::new(match format {
+            cli::EclipseFormat::Text => LogFormat::Text,
+            cli::EclipseFormat::Jsonl => LogFormat::JsonLines,
+            cli::EclipseFormat::Csv => LogFormat::Csv,
+        })));
+    }
+
+    trace!("Making observations");
+    program.make_observations(
+        start_time,
+        end_time,
+        if step_size == 0 {
+            None
+        } else {
+            Some(step_size)
+        },
+    );
+    trace!("Finished Observations");
+    Ok(())
+}
+
+/// Prints when `body` rises, sets, and transits for each observatory in the given universe
+#[allow(clippy::too_many_arguments)]
+fn almanac(
+    body: &str,
+    start_time: f64,
+    end_time: f64,
+    step_size: f64,
+    observatory: Option<&str>,
+    light_time: bool,
+    universe: Option<&PathBuf>,
+    observatories: Option<&PathBuf>,
+    program: &str,
+) -> Result<(), Box<dyn Error>> {
+    trace!("Entered Almanac function in binary");
+    let mut program = load_program(universe, observatories, program)?;
+    if light_time {
+        program.set_light_time(true);
+    }
+    let Some(target) = find_body(program.get_root_body(), body) else {
+        error!("Cannot find body: {body}");
+        return Err(Box::new(NotFoundError {
+            name: body.to_string(),
+        }));
+    };
+    let target_name = target
+        .read()
+        .map_or_else(|_| body.to_string(), |b| b.get_name().to_string());
+
+    // Float may be f32 or f64 depending on features
+    #[allow(clippy::cast_possible_truncation, clippy::unnecessary_cast)]
+    let (start_time, end_time, step_size) = (
+        start_time as astrograph::Float,
+        end_time as astrograph::Float,
+        step_size as astrograph::Float,
+    );
+
+    println!("observatory,body,event,time,altitude,azimuth");
+    for o in program
+        .get_observatories()
+        .iter()
+        .filter(|o| observatory.is_none_or(|name| o.get_name() == name))
+    {
+        // The same correction as the observations made by the program
+        let light_time = program.has_light_time() || o.has_light_time();
+        let Some(almanac) = o.get_almanac(&target, start_time, end_time, step_size, light_time)
+        else {
+            warn!("{body} cannot rise or set for {}", o.get_name());
+            continue;
+        };
+        info!(
+            "{body} is {:?} for {} between {start_time} and {end_time}",
+            almanac.visibility,
+            o.get_name()
+        );
+        for event in almanac.events {
+            println!(
+                "{},{target_name},{},{},{},{}",
+                o.get_name(),
+                event.kind,
+                event.time,
+                event.altitude,
+                event.azimuth
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Finds a body by name, or by its ID with the indices separated by dashes
+fn find_body(root: &astrograph::body::Arc, query: &str) -> Option<astrograph::body::Arc> {
+    let matches = root.read().ok().is_some_and(|body| {
+        let id: Vec<String> = body.get_id().iter().map(ToString::to_string).collect();
+        *body.get_name() == *query || id.join("-") == query
+    });
+    if matches {
+        return Some(root.clone());
+    }
+
+    let children = root.read().ok()?.get_children().clone();
+    children.iter().find_map(|child| find_body(child, query))
+}
+
+/// Reads a JSON file, resolving relative paths inside it (e.g. to tabulated ephemerides or horizon
+/// masks) against the directory of the file
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    let json = fs::read_to_string(path).ok()?;
+    let directory = path.parent().unwrap_or_else(|| Path::new("."));
+    csv::with_base_directory(directory, || serde_json::from_str(&json).ok())
+}
+
+/// Loads a program from a universe and observatories, or otherwise from a program file
+fn load_program(
+    universe: Option<&PathBuf>,
+    observatories: Option<&PathBuf>,
+    program: &str,
+) -> Result<Program, Box<dyn Error>> {
     let program: Program = if let (Some(universe), Some(observatories)) = (
-        universe
-            .and_then(|path| fs::read_to_string(path).ok())
-            .and_then(|json| {
-                let res: Result<Body, _> = serde_json::from_str(&json);
-                res.ok()
-            }),
-        observatories
-            .and_then(|path| fs::read_to_string(path).ok())
-            .and_then(|json| {
-                let res: Result<Vec<WeakObservatory>, _> = serde_json::from_str(&json);
-                res.ok()
-            }),
+        universe.and_then(|path| read_json::<Body>(path)),
+        observatories.and_then(|path| read_json::<Vec<WeakObservatory>>(path)),
     ) {
         trace!("Reading from parts");
         let root: astrograph::body::Arc = Arc::new(RwLock::new(universe));
@@ -184,9 +348,6 @@ fn simulate(
 
         trace!("Building the program around these observatories and bodies");
         let mut program_builder = ProgramBuilder::default();
-        program_builder
-            .add_output(Box::new(Svg::new(StatelessOrthographic())))
-            .output_file_root(output.to_owned());
         debug!(
             "Created a program from parts with {} observatories",
             observatories.len()
@@ -198,13 +359,8 @@ fn simulate(
         }
 
         program_builder.root_body(root).build().unwrap()
-    } else if let Some(mut program) = fs::read_to_string(program)
-        .ok()
-        .and_then(|json| serde_json::from_str::<Program>(&json).ok())
-    {
+    } else if let Some(program) = read_json::<Program>(Path::new(program)) {
         trace!("Reading from program file");
-        program.add_output(Box::new(Svg::new(StatelessOrthographic())));
-        program.set_output_path(output);
         program
     } else if let (Some(universe), Some(observatories)) = (
         universe.map(|x| x.to_str().unwrap_or("UNPRINTABLE PATH").to_string()),
@@ -221,18 +377,7 @@ fn simulate(
         }));
     };
 
-    trace!("Making observations");
-    program.make_observations(
-        start_time,
-        end_time,
-        if step_size == 0 {
-            None
-        } else {
-            Some(step_size)
-        },
-    );
-    trace!("Finished Observations");
-    Ok(())
+    Ok(program)
 }
 
 #[derive(Clone, Debug)]
@@ -251,3 +396,22 @@ impl Error for ReadError {
         None
     }
 }
+
+/// A body that was asked for isn't in the universe
+#[derive(Clone, Debug)]
+struct NotFoundError {
+    /// Name or ID of the missing body
+    name: String,
+}
+
+impl Display for NotFoundError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unable to find body {}", self.name)
+    }
+}
+
+impl Error for NotFoundError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        None
+    }
+}
@@ -0,0 +1,53 @@
+use astrograph::{dynamic::keplerian::Keplerian, dynamic::Dynamic, Float};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn keplerian_offset(c: &mut Criterion) {
+    for eccentricity in [0.0167, 0.5, 0.967] {
+        let orbit = Keplerian::new_with_period(eccentricity, 499.0, 0.1, 0.2, 0.3, 0.4, 8_766.0);
+        c.bench_function(&format!("keplerian offset e={eccentricity}"), |b| {
+            b.iter(|| {
+                for time in 0_u16..1_000 {
+                    black_box(orbit.get_offset(black_box(Float::from(time) * 8.766)));
+                }
+            });
+        });
+    }
+}
+
+/// The fixed point iteration that was used before Halley's method, kept as a baseline
+fn fixed_point_eccentric_anomaly(eccentricity: Float, mean_anomaly: Float) -> Float {
+    let mut result = mean_anomaly;
+    for _ in 0..20 {
+        result = mean_anomaly + eccentricity * result.sin();
+    }
+
+    result
+}
+
+fn kepler_equation(c: &mut Criterion) {
+    for eccentricity in [0.0167, 0.5, 0.967] {
+        let orbit = Keplerian::new_with_period(eccentricity, 499.0, 0.1, 0.2, 0.3, 0.4, 8_766.0);
+        let mut group = c.benchmark_group(format!("kepler equation e={eccentricity}"));
+        group.bench_function("halley", |b| {
+            b.iter(|| {
+                for i in 0_u16..1_000 {
+                    black_box(orbit.get_eccentric_anomaly(black_box(Float::from(i) / 100.0)));
+                }
+            });
+        });
+        group.bench_function("fixed point (baseline)", |b| {
+            b.iter(|| {
+                for i in 0_u16..1_000 {
+                    black_box(fixed_point_eccentric_anomaly(
+                        eccentricity,
+                        black_box(Float::from(i) / 100.0),
+                    ));
+                }
+            });
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, keplerian_offset, kepler_equation);
+criterion_main!(benches);
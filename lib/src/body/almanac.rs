@@ -0,0 +1,73 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::Float;
+
+/// Something that happens to a body in the sky of an observatory
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AlmanacEventKind {
+    /// The body rises above the horizon
+    Rise,
+    /// The body sets below the horizon
+    Set,
+    /// The body is at its highest, i.e. it culminates
+    UpperTransit,
+    /// The body is at its lowest
+    LowerTransit,
+}
+
+impl Display for AlmanacEventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rise => write!(f, "rise"),
+            Self::Set => write!(f, "set"),
+            Self::UpperTransit => write!(f, "upper transit"),
+            Self::LowerTransit => write!(f, "lower transit"),
+        }
+    }
+}
+
+/// A rise, set, or transit of a body, see [`super::observatory::Observatory::get_almanac`]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlmanacEvent {
+    /// What happens
+    pub kind: AlmanacEventKind,
+    /// Unit: hours
+    ///
+    /// Definition: When it happens
+    pub time: Float,
+    /// Unit: radians
+    ///
+    /// Definition: Apparent altitude of the body above the mathematical horizon, including
+    /// refraction
+    pub altitude: Float,
+    /// Unit: radians
+    ///
+    /// Definition: Azimuthal angle of the body in the local coordinates of the observatory
+    pub azimuth: Float,
+}
+
+/// Whether a body crosses the horizon during the time range of an [`Almanac`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Visibility {
+    /// The body rises or sets at least once
+    RisesAndSets,
+    /// The body never sets, e.g. it is circumpolar
+    AlwaysAbove,
+    /// The body never rises
+    AlwaysBelow,
+}
+
+/// The rises, sets, and transits of a body seen from an observatory over a range of time
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Almanac {
+    /// Events in the order they happen
+    pub events: Vec<AlmanacEvent>,
+    /// Whether the body crosses the horizon at all
+    pub visibility: Visibility,
+}
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{consts::float, Float};
+
+/// The air above the surface of a body, which bends light near the horizon ([atmospheric
+/// refraction](https://en.wikipedia.org/wiki/Atmospheric_refraction)) and dims light passing
+/// through it ([extinction](https://en.wikipedia.org/wiki/Extinction_(astronomy))). Missing
+/// values default to the earth's.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Atmosphere {
+    /// Unit: kilopascals
+    ///
+    /// Definition: Air pressure at the surface
+    pressure: Float,
+    /// Unit: kelvin
+    ///
+    /// Definition: Air temperature at the surface
+    temperature: Float,
+    /// Unit: magnitudes per airmass
+    ///
+    /// Definition: How much dimmer a body appears for each atmosphere of air the light passes
+    /// through, e.g. 0.2 at zenith on a clear night on earth
+    extinction: Float,
+    /// Unit: light seconds
+    ///
+    /// Definition: Height over which the pressure falls by a factor of e, i.e. the [scale
+    /// height](https://en.wikipedia.org/wiki/Scale_height)
+    scale_height: Float,
+}
+
+impl Default for Atmosphere {
+    fn default() -> Self {
+        Self {
+            pressure: 101.0,
+            temperature: 283.0,
+            extinction: 0.2,
+            // 8.5km
+            scale_height: 2.835e-5,
+        }
+    }
+}
+
+impl Atmosphere {
+    /// Creates an atmosphere with a surface `pressure` in kilopascals, surface `temperature` in
+    /// kelvin, and an `extinction` coefficient in magnitudes per airmass. The scale height is the
+    /// earth's, see [`Self::with_scale_height`].
+    #[must_use]
+    pub fn new(pressure: Float, temperature: Float, extinction: Float) -> Self {
+        Self {
+            pressure,
+            temperature,
+            extinction,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the height (in light seconds) over which the pressure falls by a factor of e
+    #[must_use]
+    pub fn with_scale_height(mut self, scale_height: Float) -> Self {
+        self.scale_height = scale_height;
+        self
+    }
+
+    /// Gets the air above the given `elevation` (in light seconds), which has a lower pressure
+    /// and less air to dim light than at the surface
+    #[must_use]
+    pub fn at_elevation(self, elevation: Float) -> Self {
+        if elevation <= 0.0 {
+            return self;
+        }
+
+        let thinning = (-elevation / self.scale_height).exp();
+        Self {
+            pressure: self.pressure * thinning,
+            extinction: self.extinction * thinning,
+            ..self
+        }
+    }
+
+    /// Gets how far (in radians) a body at the true `altitude` (in radians) is lifted by
+    /// refraction, using [Sæmundsson's
+    /// formula](https://en.wikipedia.org/wiki/Atmospheric_refraction#Calculating_refraction)
+    /// scaled by the pressure and temperature
+    #[must_use]
+    pub fn get_refraction(&self, altitude: Float) -> Float {
+        let altitude = altitude.to_degrees();
+        // The formula is only valid down to a couple of degrees below the horizon, where bodies
+        // can't be seen anyway
+        if altitude < -2.0 {
+            return 0.0;
+        }
+
+        // Unit: arcminutes, the small constant makes the refraction zero at the zenith
+        let refraction =
+            1.02 / (altitude + 10.3 / (altitude + 5.11)).to_radians().tan() + 0.001_927_9;
+        let scale = self.pressure / 101.0 * 283.0 / self.temperature;
+        (refraction * scale / 60.0).to_radians().max(0.0)
+    }
+
+    /// Gets the apparent altitude (in radians) of a body at the true `altitude` (in radians)
+    #[must_use]
+    pub fn get_apparent_altitude(&self, altitude: Float) -> Float {
+        altitude + self.get_refraction(altitude)
+    }
+
+    /// Gets the relative length of the path light takes through the atmosphere from a body at the
+    /// apparent `altitude` (in radians), where the zenith is 1, using [Pickering's
+    /// formula](https://en.wikipedia.org/wiki/Air_mass_(astronomy)#Interpolative_formulas)
+    #[must_use]
+    pub fn get_airmass(altitude: Float) -> Float {
+        let altitude = altitude.to_degrees().clamp(0.0, 90.0);
+        1.0 / (altitude + 244.0 / (165.0 + 47.0 * altitude.powf(1.1)))
+            .to_radians()
+            .sin()
+    }
+
+    /// Gets how many magnitudes dimmer a body at the apparent `altitude` (in radians) appears
+    #[must_use]
+    pub fn get_extinction(&self, altitude: Float) -> Float {
+        self.extinction * Self::get_airmass(altitude)
+    }
+
+    /// Gets the fraction of light from a body at the apparent `altitude` (in radians) that makes
+    /// it through the atmosphere
+    #[must_use]
+    pub fn get_transmission(&self, altitude: Float) -> Float {
+        (10.0 as Float).powf(-0.4 * self.get_extinction(altitude))
+    }
+}
+
+/// Converts a polar angle from the zenith to an altitude above the horizon
+#[must_use]
+pub(crate) fn to_altitude(polar_angle: Float) -> Float {
+    float::FRAC_PI_2 - polar_angle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Atmosphere;
+    use crate::Float;
+
+    #[test]
+    fn refraction() {
+        let atmosphere = Atmosphere::default();
+
+        // About half a degree at the horizon, and nothing at the zenith
+        assert_float_absolute_eq!(atmosphere.get_refraction(0.0).to_degrees(), 0.48, 0.02);
+        assert_float_absolute_eq!(
+            atmosphere.get_refraction((90.0 as Float).to_radians()),
+            0.0,
+            1e-6
+        );
+        assert!(atmosphere.get_apparent_altitude((-0.3 as Float).to_radians()) > 0.0);
+
+        // Thinner air bends light less
+        let thin = Atmosphere::new(0.6, 210.0, 0.0);
+        assert!(thin.get_refraction(0.0) < atmosphere.get_refraction(0.0) / 50.0);
+    }
+
+    #[test]
+    fn extinction() {
+        let atmosphere = Atmosphere::default();
+
+        assert_float_absolute_eq!(
+            Atmosphere::get_airmass((90.0 as Float).to_radians()),
+            1.0,
+            1e-3
+        );
+        assert_float_absolute_eq!(Atmosphere::get_airmass(0.0), 38.0, 1.0);
+        assert_float_absolute_eq!(
+            atmosphere.get_extinction((90.0 as Float).to_radians()),
+            0.2,
+            1e-3
+        );
+
+        // Bodies near the horizon are dimmer
+        assert!(atmosphere.get_transmission(0.05) < atmosphere.get_transmission(1.0));
+        assert!(atmosphere.get_transmission(1.0) < 1.0);
+    }
+
+    #[test]
+    fn serialization() {
+        let atmosphere: Atmosphere = serde_json::from_str(r#"{"extinction":0.3}"#).unwrap();
+        assert_eq!(atmosphere, Atmosphere::new(101.0, 283.0, 0.3));
+    }
+
+    #[test]
+    fn thinner_at_elevation() {
+        let atmosphere = Atmosphere::default();
+        assert_eq!(atmosphere.at_elevation(0.0), atmosphere);
+
+        // One scale height up
+        let high = atmosphere.at_elevation(2.835e-5);
+        assert_float_relative_eq!(high.pressure, 101.0 / core::f64::consts::E as Float, 1e-4);
+        assert!(high.get_refraction(0.0) < atmosphere.get_refraction(0.0));
+        assert!(high.get_transmission(0.1) > atmosphere.get_transmission(0.1));
+    }
+}
@@ -0,0 +1,288 @@
+use std::{fmt::Display, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{consts::float, csv, Float};
+
+/// The outline of terrain and buildings around an observatory, bodies below the outline can't be
+/// seen. Altitudes between points are linearly interpolated, wrapping around from the last point
+/// to the first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "IntermediateHorizonMask", into = "IntermediateHorizonMask")]
+pub struct HorizonMask {
+    /// Unit: radians
+    ///
+    /// Definition: Increasing azimuths (the [`coordinates::prelude::Spherical::azimuthal_angle`]
+    /// of observations) of each point, in `[0, TAU)`.
+    azimuths: Vec<Float>,
+    /// Unit: radians
+    ///
+    /// Definition: Altitude of the outline above the mathematical horizon at the matching azimuth
+    /// in [`Self::azimuths`], negative values let observatories on peaks see below it.
+    altitudes: Vec<Float>,
+    /// The CSV file the mask was loaded from, if any. Used to avoid writing the whole mask when
+    /// serializing.
+    source: Option<PathBuf>,
+}
+
+/// Errors that can occur while building a [`HorizonMask`]
+#[derive(Debug)]
+pub enum HorizonMaskError {
+    /// The mask has no points
+    Empty,
+    /// The point at the given index is not after the previous point
+    Unordered(usize),
+    /// The CSV file could not be read
+    Io(std::io::Error),
+    /// The given line (starting at 1) of a CSV file could not be parsed
+    Parse(usize),
+}
+
+impl Display for HorizonMaskError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Horizon mask must have at least one point"),
+            Self::Unordered(i) => write!(
+                f,
+                "Point {i} of the horizon mask is not after the previous point"
+            ),
+            Self::Io(e) => write!(f, "Could not read horizon mask: {e}"),
+            Self::Parse(line) => write!(
+                f,
+                "Could not parse line {line} of the horizon mask, expected `azimuth,altitude`"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HorizonMaskError {}
+
+impl HorizonMask {
+    /// Generates a new horizon mask from `(azimuth, altitude)` points in radians. Azimuths are
+    /// wrapped into `[0, TAU)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no points, or the wrapped azimuths are not strictly
+    /// increasing.
+    pub fn new(points: Vec<(Float, Float)>) -> Result<Self, HorizonMaskError> {
+        if points.is_empty() {
+            return Err(HorizonMaskError::Empty);
+        }
+
+        let (azimuths, altitudes): (Vec<Float>, Vec<Float>) = points
+            .into_iter()
+            .map(|(azimuth, altitude)| (azimuth.rem_euclid(float::TAU), altitude))
+            .unzip();
+        if let Some(i) = azimuths.windows(2).position(|w| w[0] >= w[1]) {
+            return Err(HorizonMaskError::Unordered(i + 1));
+        }
+
+        Ok(Self {
+            azimuths,
+            altitudes,
+            source: None,
+        })
+    }
+
+    /// Loads a horizon mask from a CSV file with the columns `azimuth,altitude` in radians. Blank
+    /// lines, lines starting with `#`, and a single header row before the points are ignored.
+    /// Relative paths are resolved against the directory given to [`csv::with_base_directory`],
+    /// or the working directory otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, a row cannot be parsed, or the points are not
+    /// valid (see [`Self::new`]).
+    pub fn from_csv<T: Into<PathBuf>>(path: T) -> Result<Self, HorizonMaskError> {
+        let path = path.into();
+        let points = csv::read_rows(&path, 2)
+            .map_err(|error| match error {
+                csv::ReadError::Io(e) => HorizonMaskError::Io(e),
+                csv::ReadError::Parse(line) => HorizonMaskError::Parse(line),
+            })?
+            .into_iter()
+            .map(|row| (row[0], row[1]))
+            .collect();
+
+        let mut result = Self::new(points)?;
+        result.source = Some(path);
+        Ok(result)
+    }
+
+    /// Gets the altitude (in radians) of the outline at the given `azimuth` (in radians)
+    #[must_use]
+    pub fn get_altitude(&self, azimuth: Float) -> Float {
+        let azimuth = azimuth.rem_euclid(float::TAU);
+        let count = self.azimuths.len();
+
+        // The first point after `azimuth`, wrapping around to the first point
+        let next = self.azimuths.partition_point(|a| *a <= azimuth);
+        let previous = (next + count - 1) % count;
+        let next = next % count;
+
+        let start = self.azimuths[previous];
+        let width = (self.azimuths[next] - start).rem_euclid(float::TAU);
+        if width == 0.0 {
+            return self.altitudes[previous];
+        }
+
+        let s = (azimuth - start).rem_euclid(float::TAU) / width;
+        self.altitudes[previous] + (self.altitudes[next] - self.altitudes[previous]) * s
+    }
+}
+
+/// Serialized form of [`HorizonMask`], either a path to a CSV file or an inline list of points
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum IntermediateHorizonMask {
+    /// Path to a CSV file with the columns `azimuth,altitude`, see [`HorizonMask::from_csv`]
+    File {
+        /// Path to the CSV file
+        file: PathBuf,
+    },
+    /// Inline list of points
+    Points {
+        /// `[azimuth, altitude]` points
+        points: Vec<[Float; 2]>,
+    },
+}
+
+impl TryFrom<IntermediateHorizonMask> for HorizonMask {
+    type Error = HorizonMaskError;
+
+    fn try_from(value: IntermediateHorizonMask) -> Result<Self, Self::Error> {
+        match value {
+            IntermediateHorizonMask::File { file } => Self::from_csv(file),
+            IntermediateHorizonMask::Points { points } => Self::new(
+                points
+                    .into_iter()
+                    .map(|[azimuth, altitude]| (azimuth, altitude))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<HorizonMask> for IntermediateHorizonMask {
+    fn from(value: HorizonMask) -> Self {
+        if let Some(file) = value.source {
+            IntermediateHorizonMask::File { file }
+        } else {
+            IntermediateHorizonMask::Points {
+                points: value
+                    .azimuths
+                    .into_iter()
+                    .zip(value.altitudes)
+                    .map(|(azimuth, altitude)| [azimuth, altitude])
+                    .collect(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::{HorizonMask, HorizonMaskError};
+    use crate::{consts::float, Float};
+
+    #[test]
+    fn interpolation_wraps_around() {
+        let mask = HorizonMask::new(vec![(0.0, 0.1), (3.0, 0.3), (2.0, 0.2)]).unwrap_err();
+        assert!(matches!(mask, HorizonMaskError::Unordered(2)));
+
+        let mask = HorizonMask::new(vec![(1.0, 0.1), (3.0, 0.3), (5.0, 0.2)]).unwrap();
+        assert_float_absolute_eq!(mask.get_altitude(1.0), 0.1, 1e-6);
+        assert_float_absolute_eq!(mask.get_altitude(2.0), 0.2, 1e-6);
+        assert_float_absolute_eq!(mask.get_altitude(5.0 + float::TAU), 0.2, 1e-5);
+
+        // Between the last and first points, on both sides of zero
+        let gap = float::TAU - 4.0;
+        let expected = |azimuth: Float| 0.2 + (0.1 - 0.2) * (azimuth - 5.0) / gap;
+        assert_float_absolute_eq!(mask.get_altitude(6.0), expected(6.0), 1e-5);
+        assert_float_absolute_eq!(mask.get_altitude(0.5), expected(0.5 + float::TAU), 1e-5);
+
+        // A single point is a flat horizon
+        let flat = HorizonMask::new(vec![(2.0, 0.05)]).unwrap();
+        assert_float_absolute_eq!(flat.get_altitude(5.0), 0.05);
+    }
+
+    #[test]
+    fn serialization() {
+        let mask: HorizonMask =
+            serde_json::from_str(r#"{"points":[[0.0,0.1],[3.0,0.2]]}"#).unwrap();
+        assert_float_absolute_eq!(mask.get_altitude(1.5), 0.15, 1e-6);
+        let new: HorizonMask =
+            serde_json::from_str(&serde_json::to_string(&mask).unwrap()).unwrap();
+        assert_eq!(new, mask);
+
+        let directory = crate::testing::get_temp_directory("horizon-mask");
+        let path = directory.join("horizon.csv");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            "azimuth,altitude\n# A hill to the east\n0.0,0.0\n1.5,0.4\n3.0,0.0"
+        )
+        .unwrap();
+        drop(file);
+
+        let json = format!(r#"{{"file":{:?}}}"#, path.to_str().unwrap());
+        let mask: HorizonMask = serde_json::from_str(&json).unwrap();
+        assert_float_absolute_eq!(mask.get_altitude(1.5), 0.4, 1e-6);
+        assert!(serde_json::to_string(&mask).unwrap().contains("file"));
+
+        // Relative paths are resolved against the base directory, and written back as given
+        let json = r#"{"file":"horizon.csv"}"#;
+        assert!(serde_json::from_str::<HorizonMask>(json).is_err());
+        let mask = crate::csv::with_base_directory(&directory, || {
+            serde_json::from_str::<HorizonMask>(json)
+        })
+        .unwrap();
+        assert_float_absolute_eq!(mask.get_altitude(1.5), 0.4, 1e-6);
+        assert_eq!(serde_json::to_string(&mask).unwrap(), json);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn csv_errors() {
+        let directory = crate::testing::get_temp_directory("horizon-mask-errors");
+        let path = directory.join("horizon.csv");
+
+        // Only one header row, so a misformatted file doesn't silently lose its first points
+        std::fs::write(
+            &path,
+            "azimuth,altitude
+not,numbers
+0.0,0.1
+",
+        )
+        .unwrap();
+        assert!(matches!(
+            HorizonMask::from_csv(&path),
+            Err(HorizonMaskError::Parse(2))
+        ));
+
+        // Rows need both columns
+        std::fs::write(
+            &path,
+            "0.0,0.1
+1.0
+",
+        )
+        .unwrap();
+        assert!(matches!(
+            HorizonMask::from_csv(&path),
+            Err(HorizonMaskError::Parse(2))
+        ));
+
+        assert!(matches!(
+            HorizonMask::from_csv(directory.join("missing.csv")),
+            Err(HorizonMaskError::Io(_))
+        ));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -1,3 +1,9 @@
+/// Contains the rises, sets, and transits of bodies seen from an observatory
+pub mod almanac;
+/// Contains the air above the surface of a body that bends and dims light
+pub mod atmosphere;
+/// Contains the outline of terrain around an observatory
+pub mod horizon;
 /// Contains the definition of observatories that sit on the surface of a body and observe the
 /// motion of other bodies
 pub mod observatory;
@@ -6,18 +12,24 @@ pub mod rotating;
 
 use std::sync::{Arc as StdArc, RwLock, Weak as StdWeak};
 
-use coordinates::prelude::{ThreeDimensionalConsts, Vector3};
+use atmosphere::Atmosphere;
+use coordinates::{
+    prelude::{ThreeDimensionalConsts, Vector3},
+    traits::{Dot, Magnitude},
+};
 use derive_builder::Builder;
 use log::{trace, warn};
 use rotating::Rotating;
 use serde::{Deserialize, Serialize};
 
-use crate::{dynamic::Dynamic, EllipticObservation, Float};
+use crate::{consts, dynamic::Dynamic, EllipticObservation, Float};
 
 /// A convenience wrapper for [`std::sync::Arc`]`<`[`std::sync::RwLock`]`<`[`self::Body`]`>>`
 pub type Arc = StdArc<RwLock<Body>>;
 /// A convenience wrapper for [`std::sync::Weak`]`<`[`std::sync::RwLock`]`<`[`self::Body`]`>>`
 type Weak = StdWeak<RwLock<Body>>;
+/// A value taken from a dynamic that can be summed along the body tree, e.g. offsets or velocities
+type ValueFn = fn(&dyn Dynamic, Float) -> Vector3<Float>;
 
 /// A representation of a body in the simulation, such as a star, planet, center of mass, or moon.
 #[derive(Debug, Clone, Deserialize, Serialize, Builder)]
@@ -35,12 +47,18 @@ pub struct Body {
     /// If the body has any o1fservatories it is highly recommended to initialize this.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) rotation: Option<Rotating>,
-    // Getting some parameters ready for a next version
-    // /// Mass of the body in jupiter masses
-    //mass: Float,
+    /// Mass of the body in jupiter masses, only needed for
+    /// [N-body integration](crate::dynamic::n_body::NBody)
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[builder(default)]
+    pub(crate) mass: Option<Float>,
     /// Radius of the body in light seconds
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) radius: Option<Float>,
+    /// Air above the surface that observatories on this body look through
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[builder(default)]
+    pub(crate) atmosphere: Option<Atmosphere>,
     //color: [u8,h8,u8],
     #[serde(skip_serializing_if = "Name::is_calculated", default)]
     /// (Preferably unique) Name of the body. Is either user defined or generated from the ID of
@@ -48,6 +66,23 @@ pub struct Body {
     pub(crate) name: Name,
 }
 
+/// Moves `location` to where it appears to an observer moving at `beta` (velocity as a fraction of
+/// the speed of light), using the relativistic [aberration
+/// formula](https://en.wikipedia.org/wiki/Aberration_(astronomy)#Relativistic_aberration)
+fn aberrate(location: Vector3<Float>, beta: Vector3<Float>) -> Vector3<Float> {
+    let distance = location.magnitude();
+    if distance == 0.0 {
+        return location;
+    }
+
+    let direction = location / distance;
+    let inverse_gamma = (1.0 - beta.dot(&beta)).sqrt();
+    let cos = direction.dot(&beta);
+    let apparent =
+        (direction * inverse_gamma + beta + beta * (cos / (1.0 + inverse_gamma))) / (1.0 + cos);
+    apparent * distance
+}
+
 impl From<Body> for Arc {
     fn from(value: Body) -> Self {
         let result = Arc::new(RwLock::new(value));
@@ -56,13 +91,14 @@ impl From<Body> for Arc {
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase", untagged)]
 pub enum Name {
     Named(StdArc<str>),
     #[serde(skip)]
     Id(StdArc<str>),
     #[serde(skip)]
+    #[default]
     Unknown,
 }
 
@@ -82,12 +118,6 @@ impl Name {
     }
 }
 
-impl Default for Name {
-    fn default() -> Self {
-        Self::Unknown
-    }
-}
-
 impl<T: From<StdArc<str>>> From<Name> for Option<T> {
     fn from(value: Name) -> Self {
         match value {
@@ -126,7 +156,9 @@ impl Body {
             children: Vec::new(),
             dynamic: Box::new(dynamic),
             rotation: None,
+            mass: None,
             radius: None,
+            atmosphere: None,
             name: Name::Unknown,
         }));
         if let Some(p) = parent {
@@ -157,6 +189,16 @@ impl Body {
             if let Name::Unknown = child.name {
                 child.name = Name::Id(observatory::to_name(&id).into());
             }
+
+            let child = &mut *child;
+            if let Some(rotation) = &mut child.rotation {
+                if !rotation.lock_to_orbit(child.dynamic.as_ref()) {
+                    warn!(
+                        "Tidally locked rotation needs a keplerian or circular orbit, not {:?}",
+                        child.dynamic
+                    );
+                }
+            }
         }
 
         // A weak pointer to this body.
@@ -168,6 +210,37 @@ impl Body {
         }
     }
 
+    /// Copies the tree under `this` into a new tree that shares no bodies with the original, so
+    /// the copy can be changed (e.g. by [`crate::dynamic::n_body::NBody::integrate`]) without
+    /// affecting the original. The copy of `this` has no parent.
+    #[must_use]
+    pub fn deep_clone(this: &Arc) -> Arc {
+        Self::deep_clone_with_parent(this, None)
+    }
+
+    /// Copies the tree under `this`, giving the copy of `this` the given parent
+    fn deep_clone_with_parent(this: &Arc, parent: Option<Weak>) -> Arc {
+        let Ok(body) = this.read() else {
+            return this.clone();
+        };
+        let copy = Arc::new(RwLock::new(Self {
+            parent,
+            children: Vec::with_capacity(body.children.len()),
+            ..body.clone()
+        }));
+
+        let weak = StdArc::downgrade(&copy);
+        let children = body
+            .children
+            .iter()
+            .map(|child| Self::deep_clone_with_parent(child, Some(weak.clone())))
+            .collect();
+        if let Ok(mut lock) = copy.write() {
+            lock.children = children;
+        }
+        copy
+    }
+
     /// Returns the indexes of each child that must be decended into to reach this body.
     ///
     /// # Examples
@@ -229,6 +302,28 @@ impl Body {
         }
     }
 
+    /// Get the mass of this body in jupiter masses, if it is known
+    #[must_use]
+    pub fn get_mass(&self) -> Option<Float> {
+        self.mass
+    }
+
+    /// Set the mass of this body in jupiter masses
+    pub fn set_mass(&mut self, mass: Option<Float>) {
+        self.mass = mass;
+    }
+
+    /// Get the atmosphere of this body, if it has one
+    #[must_use]
+    pub fn get_atmosphere(&self) -> Option<&Atmosphere> {
+        self.atmosphere.as_ref()
+    }
+
+    /// Set the atmosphere of this body
+    pub fn set_atmosphere(&mut self, atmosphere: Option<Atmosphere>) {
+        self.atmosphere = atmosphere;
+    }
+
     #[must_use]
     pub fn get_children(&self) -> &Vec<Arc> {
         &self.children
@@ -243,32 +338,158 @@ impl Body {
 
     #[must_use]
     pub fn get_observations_from_here(&self, time: Float) -> Vec<EllipticObservation> {
-        let mut results = self.traverse_down(time, Vector3::ORIGIN);
+        self.get_relative_from_here(time, |dynamic, time| dynamic.get_offset(time))
+    }
+
+    /// Returns the location of every other body relative to this body, as seen by light arriving
+    /// at this body at `time`. i.e. each body is where it was when the light left it, correcting
+    /// for [light-travel time](https://en.wikipedia.org/wiki/Light-time_correction).
+    #[must_use]
+    pub fn get_delayed_observations_from_here(&self, time: Float) -> Vec<EllipticObservation> {
+        self.get_apparent_observations_from_here(time, true, false)
+    }
+
+    /// Returns the location of every other body relative to this body, optionally corrected for
+    /// light-travel time (see [`Self::get_delayed_observations_from_here`]) and for [stellar
+    /// aberration](https://en.wikipedia.org/wiki/Aberration_(astronomy)) caused by the velocity of
+    /// this body relative to the root of the tree.
+    #[must_use]
+    pub fn get_apparent_observations_from_here(
+        &self,
+        time: Float,
+        light_time: bool,
+        aberration: bool,
+    ) -> Vec<EllipticObservation> {
+        let mut results = self.get_inertial_observations_from_here(time, light_time, aberration);
+        if let Some(rot) = &self.rotation {
+            rot.rotate_observed_bodies_equatorial_coordinates(time, &mut results);
+        }
+        results
+    }
+
+    /// Same as [`Self::get_apparent_observations_from_here`] without the rotation of this body
+    /// applied, i.e. in the same orientation as the root of the tree
+    #[must_use]
+    pub fn get_inertial_observations_from_here(
+        &self,
+        time: Float,
+        light_time: bool,
+        aberration: bool,
+    ) -> Vec<EllipticObservation> {
+        /// Each iteration shrinks the error by a factor of the relative speed over the speed of
+        /// light, so a few are plenty
+        const ITERATIONS: u8 = 3;
+
+        let mut results =
+            self.get_unrotated_from_here(time, |dynamic, time| dynamic.get_offset(time));
+
+        if light_time {
+            let here = self.get_absolute_offset(time);
+            for (body, location) in &mut results {
+                if let Ok(body) = body.read() {
+                    for _ in 0..ITERATIONS {
+                        let delay = location.magnitude() / consts::SPEED_OF_LIGHT;
+                        *location = body.get_absolute_offset(time - delay) - here;
+                    }
+                }
+            }
+        }
+
+        if aberration {
+            let beta = self.get_absolute_velocity(time) / consts::SPEED_OF_LIGHT;
+            for (_, location) in &mut results {
+                *location = aberrate(*location, beta);
+            }
+        }
+
+        results
+    }
+
+    /// Returns the root of the tree that `this` body is in, i.e. the ancestor without a parent
+    #[must_use]
+    pub fn get_root(this: &Arc) -> Arc {
+        let mut root = this.clone();
+        while let Some(parent) = root
+            .read()
+            .ok()
+            .and_then(|body| body.parent.as_ref().and_then(StdWeak::upgrade))
+        {
+            root = parent;
+        }
+        root
+    }
+
+    /// Returns the location of this body relative to the root of the tree
+    #[must_use]
+    pub fn get_absolute_offset(&self, time: Float) -> Vector3<Float> {
+        self.get_absolute(time, |dynamic, time| dynamic.get_offset(time))
+    }
+
+    /// Returns the velocity of this body relative to the root of the tree, in light-seconds per
+    /// hour
+    #[must_use]
+    pub fn get_absolute_velocity(&self, time: Float) -> Vector3<Float> {
+        self.get_absolute(time, |dynamic, time| dynamic.get_velocity(time))
+    }
+
+    /// Sums `value` up the tree to find the value of this body relative to the root
+    fn get_absolute(&self, time: Float, value: ValueFn) -> Vector3<Float> {
+        self.parent
+            .as_ref()
+            .and_then(StdWeak::upgrade)
+            .map_or(Vector3::ORIGIN, |parent| {
+                parent
+                    .read()
+                    .map_or(Vector3::ORIGIN, |parent| parent.get_absolute(time, value))
+                    + value(&*self.dynamic, time)
+            })
+    }
+
+    /// Returns the velocity of every other body relative to this body, in light-seconds per hour.
+    /// Bodies are in the same order and frame as [`Self::get_observations_from_here`], but the
+    /// spin of this body is not added to the velocities.
+    #[must_use]
+    pub fn get_velocities_from_here(&self, time: Float) -> Vec<EllipticObservation> {
+        self.get_relative_from_here(time, |dynamic, time| dynamic.get_velocity(time))
+    }
+
+    /// Sums `value` along the tree to find the value of every other body relative to this body,
+    /// e.g. [`Dynamic::get_offset`] for locations
+    fn get_relative_from_here(&self, time: Float, value: ValueFn) -> Vec<EllipticObservation> {
+        let mut results = self.get_unrotated_from_here(time, value);
+        if let Some(rot) = &self.rotation {
+            // Rotate observations according to axial tilt and time of day
+            rot.rotate_observed_bodies_equatorial_coordinates(time, &mut results);
+        }
+        results
+    }
+
+    /// Same as [`Self::get_relative_from_here`] without the rotation of this body applied
+    fn get_unrotated_from_here(&self, time: Float, value: ValueFn) -> Vec<EllipticObservation> {
+        let mut results = self.traverse_down(time, Vector3::ORIGIN, value);
         if let Some(parent) = self.parent.clone().and_then(|p| p.upgrade()) {
             if let Ok(parent) = parent.read() {
                 // PERF: return an iterator instead of copying all elements into a single vector
                 results.extend(
                     parent
-                        .traverse_up(time, Vector3::ORIGIN - self.dynamic.get_offset(time))
+                        .traverse_up(time, Vector3::ORIGIN - value(&*self.dynamic, time), value)
                         .into_iter()
                         // Remove current body from the results
                         .filter(|(b, _)| b.read().is_ok_and(|b| b.get_name() != self.get_name())),
                 );
             }
         }
-        if let Some(rot) = &self.rotation {
-            // Rotate observations according to axial tilt and time of day
-            rot.rotate_observed_bodies_equatorial_coordinates(time, &mut results);
-        }
         results
     }
 
-    /// Returns the locations of the children relative to `current_position`
+    /// Returns the locations (or other [values](ValueFn)) of the descendants relative to
+    /// `current_position`
     #[must_use]
     fn traverse_down(
         &self,
         time: Float,
         current_position: Vector3<Float>,
+        value: ValueFn,
     ) -> Vec<EllipticObservation> {
         let mut results = Vec::with_capacity(self.children.len());
 
@@ -276,9 +497,9 @@ impl Body {
         for c in &self.children {
             if let Ok(child) = c.read() {
                 // Get the child position relative to here
-                let location = child.dynamic.get_offset(time) + current_position;
+                let location = value(&*child.dynamic, time) + current_position;
                 // Add grandchildren, great-grandchildren, etc.
-                results.extend(child.traverse_down(time, location));
+                results.extend(child.traverse_down(time, location, value));
 
                 // Add that child
                 results.push((c.clone(), location));
@@ -288,18 +509,20 @@ impl Body {
         results
     }
 
-    /// Returns the location of parents relative to the `current_position`
+    /// Returns the location (or other [value](ValueFn)) of parents relative to the
+    /// `current_position`
     #[must_use]
     fn traverse_up(
         &self,
         time: Float,
         current_position: Vector3<Float>,
+        value: ValueFn,
     ) -> Vec<EllipticObservation> {
         let mut results = Vec::with_capacity(self.children.len() + 2);
         for c in &self.children {
             // Add parents and cousins
             if let Ok(child) = c.read() {
-                let child_location = current_position + child.dynamic.get_offset(time);
+                let child_location = current_position + value(&*child.dynamic, time);
                 results.push((c.clone(), child_location));
             }
         }
@@ -307,11 +530,11 @@ impl Body {
         // If the parent still exists
         if let Some(p) = &self.parent.clone().and_then(|weak| weak.upgrade()) {
             // Calculate the parent's location by getting our offset
-            let parent_location = current_position - self.dynamic.get_offset(time);
+            let parent_location = current_position - value(&*self.dynamic, time);
 
             if let Ok(parent) = p.read() {
                 // Add the grandparent, great-grandparent, etc.
-                results.append(&mut parent.traverse_up(time, parent_location));
+                results.append(&mut parent.traverse_up(time, parent_location, value));
             }
         } else {
             // This body is the root. We need to add it manually since it can't be added by a parent
@@ -336,6 +559,8 @@ impl Body {
 
 #[cfg(test)]
 mod tests {
+    use coordinates::traits::{Cross3D, Magnitude};
+
     use crate::dynamic::{fixed::Fixed, keplerian::Keplerian};
 
     use super::*;
@@ -449,6 +674,34 @@ mod tests {
             );
         }
     }
+    #[test]
+    fn relative_velocities() {
+        let star = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let planet_orbit = Keplerian::new(0.1, 500.0, 0.2, 0.3, 0.4, 0.5, 1_000.0);
+        let planet = Body::new(Some(star.clone()), planet_orbit);
+        let moon_orbit = Keplerian::new(0.05, 5.0, 0.5, 0.4, 0.3, 0.2, 1.0);
+        let moon = Body::new(Some(planet.clone()), moon_orbit);
+        Body::hydrate_all(&star, &None);
+
+        let time = 123.0;
+        let velocities = moon.read().unwrap().get_velocities_from_here(time);
+        let observations = moon.read().unwrap().get_observations_from_here(time);
+        assert_eq!(velocities.len(), observations.len());
+
+        let get = |body: &Arc| {
+            velocities
+                .iter()
+                .find(|(b, _)| StdArc::ptr_eq(b, body))
+                .unwrap()
+                .1
+        };
+
+        let expected_planet = Vector3::ORIGIN - moon_orbit.get_velocity(time);
+        let expected_star = expected_planet - planet_orbit.get_velocity(time);
+        assert!((get(&planet) - expected_planet).magnitude() < 1e-6);
+        assert!((get(&star) - expected_star).magnitude() < 1e-6);
+    }
+
     #[allow(clippy::excessive_precision)] // Tests need to pass with f64 as well as f32
     #[test]
     fn serialize_to_json_string() {
@@ -599,6 +852,24 @@ mod tests {
             num_children!(mercury, 0);
             num_children!(venus, 0);
             num_children!(earth, 1);
+
+            // Luna's elements are at 2000 Jan 0.0 UT, 36 hours before J2000, so at t=0 it is at
+            // its J2000 mean anomaly of 134.963°
+            let luna = earth.read().unwrap().children[0].clone();
+            let expected = crate::dynamic::keplerian::Keplerian::new_with_period(
+                0.0549,
+                1.28243,
+                0.089_797,
+                2.183_805,
+                5.551_254,
+                (134.963 as Float).to_radians(),
+                655.7064,
+            );
+            let offset = luna.read().unwrap().get_dynamic().get_offset(0.0);
+            assert!(
+                (offset - expected.get_offset(0.0)).magnitude() < 0.01,
+                "Luna is at {offset:?}"
+            );
             // HACK: find data for moons of MARS, JUPITER, SATURN, URANUS, and NEPTUNE.
             num_children!(mars, 0);
             // We are just going to count the galilean moons
@@ -652,4 +923,85 @@ mod tests {
         });
         matches!(Option::<StdArc<str>>::None.into(), Name::Unknown);
     }
+
+    #[test]
+    fn light_time_correction() {
+        use crate::dynamic::circular::Circular;
+
+        /// Unit: hours, ten light-hours away
+        const DELAY: Float = 10.0;
+        let root = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let observer = Body::new(Some(root.clone()), Fixed::new(Vector3::ORIGIN));
+        let _ = Body::new(
+            Some(root.clone()),
+            Circular::new_with_period(DELAY * consts::SPEED_OF_LIGHT, 0.3, 0.0, 0.0, 100.0),
+        );
+        Body::hydrate_all(&root, &None);
+        let observer = observer.read().unwrap();
+        let find_moving = |observations: Vec<EllipticObservation>| {
+            observations
+                .into_iter()
+                .find(|(_, location)| location.magnitude() > 1.0)
+                .unwrap()
+                .1
+        };
+
+        // Light from a body on a circle around the observer always takes the same time to arrive
+        for time in [0.0, 20.0, 55.0] {
+            let delayed = find_moving(observer.get_delayed_observations_from_here(time));
+            let geometric = find_moving(observer.get_observations_from_here(time - DELAY));
+            assert!((delayed - geometric).magnitude() < 1e-3 * DELAY * consts::SPEED_OF_LIGHT);
+
+            let now = find_moving(observer.get_observations_from_here(time));
+            assert!((delayed - now).magnitude() > 1.0);
+        }
+    }
+
+    #[test]
+    fn annual_aberration() {
+        use crate::dynamic::circular::Circular;
+
+        /// Unit: hours, one sidereal year
+        const YEAR: Float = 8_766.15;
+        /// Unit: arcseconds, the constant of aberration for the earth
+        const AMPLITUDE: Float = 20.4955;
+        /// Unit: light seconds
+        const AU: Float = 499.005;
+        let sun = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let earth = Body::new(
+            Some(sun.clone()),
+            Circular::new_with_period(AU, 0.0, 0.0, 0.0, YEAR),
+        );
+        // Perpendicular to, and along the direction of motion of the earth at t=0
+        let _ = Body::new(Some(sun.clone()), Fixed::new(Vector3::FORWARD * 1e6));
+        let _ = Body::new(Some(sun.clone()), Fixed::new(Vector3::from((AU, 0.0, 1e6))));
+        Body::hydrate_all(&sun, &None);
+        let earth = earth.read().unwrap();
+
+        let geometric = earth.get_observations_from_here(0.0);
+        let apparent = earth.get_apparent_observations_from_here(0.0, false, true);
+        let shifts: Vec<Float> = geometric
+            .iter()
+            .zip(&apparent)
+            .map(|((_, a), (_, b))| {
+                // Angle from the cross product is more precise than the dot product for small angles
+                (a.cross(b).magnitude() / (a.magnitude() * b.magnitude()))
+                    .asin()
+                    .to_degrees()
+                    * 3_600.0
+            })
+            .collect();
+
+        // Bodies perpendicular to the motion of the earth (the first star and the sun) are shifted
+        // by the full amount, and the star the earth is moving towards is not shifted at all
+        assert_eq!(shifts.len(), 3);
+        assert_float_absolute_eq!(shifts[0], AMPLITUDE, 0.1);
+        assert_float_absolute_eq!(shifts[1], 0.0, 0.1);
+        assert_float_absolute_eq!(shifts[2], AMPLITUDE, 0.1);
+
+        // Distances are not changed
+        for ((_, a), (_, b)) in geometric.iter().zip(&apparent) {
+            assert_float_relative_eq!(a.magnitude(), b.magnitude(), 1e-5);
+        }
+    }
 }
@@ -3,17 +3,21 @@ use log::warn;
 use quaternion::Quaternion;
 use serde::{Deserialize, Serialize};
 
-use crate::{Float, LocalObservation};
+use crate::{consts::float, event, Float, LocalObservation};
 
-use super::Arc;
+use super::{
+    almanac::{Almanac, AlmanacEvent, AlmanacEventKind, Visibility},
+    atmosphere,
+    horizon::HorizonMask,
+    Arc,
+};
 
-/// Defines a place on the surface of a body where observations are made of the motion of bodies.
+/// Defines a place on (or near) a body where observations are made of the motion of bodies.
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase", from = "WeakObservatory")]
 pub struct Observatory {
-    /// A quaternion that encodes the rotation from the given longitude and latitude to the
-    /// geographic north pole to make projections easier.
-    location: Quaternion<Float>,
+    /// Where the observatory is relative to the body, and which way it faces
+    mount: Mount,
     /// The body that observations are being made from
     body: Arc,
     /// Name of the observatory, either user defined or derived from the body ID, latitude and
@@ -22,6 +26,61 @@ pub struct Observatory {
 
     /// List of constellations that could be visible from this observatory
     constellations: Vec<crate::constellation::Constellation>,
+    /// If set, bodies are observed where they were when their light left them, see
+    /// [`super::Body::get_delayed_observations_from_here`]
+    light_time: bool,
+    /// If set, bodies are observed where they appear due to the motion of the observatory, see
+    /// [`super::Body::get_apparent_observations_from_here`]
+    aberration: bool,
+    /// Terrain around the observatory that hides bodies, if not set everything above the
+    /// mathematical horizon can be seen
+    horizon: Option<HorizonMask>,
+    /// Unit: light seconds
+    ///
+    /// Definition: Height of the observatory above the surface of the body, e.g. for mountaintops
+    /// or aircraft
+    elevation: Float,
+}
+
+/// Where an [`Observatory`] is relative to its body
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum Mount {
+    /// On the surface of the body, facing straight up. The quaternion encodes the rotation from
+    /// the given longitude and latitude to the geographic north pole to make projections easier.
+    Surface(Quaternion<Float>),
+    /// Floating freely near the body, unaffected by the body's rotation
+    Free {
+        /// Unit: light seconds
+        ///
+        /// Definition: Offset from the centre of the body, in the orientation of the root of the
+        /// tree
+        offset: Vector3<Float>,
+        /// Which way the observatory faces
+        pointing: Pointing,
+    },
+}
+
+/// Which way a free-floating observatory (see [`Observatory::new_free`]) faces. The direction it
+/// faces is the centre (positive z axis) of its observations.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Pointing {
+    /// A fixed direction in the orientation of the root of the tree, like a camera on a star
+    /// tracker
+    #[serde(rename_all = "camelCase")]
+    Equatorial {
+        /// Unit: radians
+        ///
+        /// Definition: Angle around the z axis from the x axis
+        right_ascension: Float,
+        /// Unit: radians
+        ///
+        /// Definition: Angle above the xy plane
+        declination: Float,
+    },
+    /// Always faces the given body
+    LookAt(Arc),
 }
 
 impl Observatory {
@@ -36,36 +95,182 @@ impl Observatory {
         let location: Vector3<Float> = location.into();
 
         Self {
-            location: quaternion::rotation_from_to(location.into(), Vector3::UP.into()),
+            mount: Mount::Surface(quaternion::rotation_from_to(
+                location.into(),
+                Vector3::UP.into(),
+            )),
             body,
             name,
             constellations,
+            light_time: false,
+            aberration: false,
+            horizon: None,
+            elevation: 0.0,
+        }
+    }
+
+    /// Sets the height of the observatory above the surface of the body, in light seconds
+    pub fn set_elevation(&mut self, elevation: Float) {
+        self.elevation = elevation;
+    }
+
+    /// Gets the height of the observatory above the surface of the body, in light seconds
+    #[must_use]
+    pub fn get_elevation(&self) -> Float {
+        self.elevation
+    }
+
+    /// Gets how far (in radians) below the mathematical horizon the edge of a body with the given
+    /// `radius` is when seen from this observatory's elevation, i.e. the [dip of the
+    /// horizon](https://en.wikipedia.org/wiki/Horizon#Effect_of_atmospheric_refraction). A body
+    /// without a radius is a point with no surface to curve away, so it has no dip
+    fn get_horizon_dip(&self, radius: Option<Float>) -> Float {
+        match radius {
+            Some(radius) if radius > 0.0 && self.elevation > 0.0 => {
+                (radius / (radius + self.elevation)).acos()
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Sets the terrain around the observatory that hides bodies behind it
+    pub fn set_horizon(&mut self, horizon: Option<HorizonMask>) {
+        self.horizon = horizon;
+    }
+
+    /// Gets the terrain around the observatory that hides bodies behind it, if any
+    #[must_use]
+    pub fn get_horizon(&self) -> Option<&HorizonMask> {
+        self.horizon.as_ref()
+    }
+
+    /// Generates an observatory floating `offset` light seconds away from the given body (in the
+    /// orientation of the root of the tree), facing in the direction given by `pointing`. Free
+    /// observatories can see the body they float near, and are not affected by horizons,
+    /// elevation, or the atmosphere of the body.
+    #[must_use]
+    pub fn new_free(
+        offset: Vector3<Float>,
+        pointing: Pointing,
+        body: Arc,
+        name: Result<String, Vec<usize>>,
+        constellations: Vec<crate::constellation::Constellation>,
+    ) -> Self {
+        Self {
+            mount: Mount::Free { offset, pointing },
+            ..Self::new(Spherical::UP, body, name, constellations)
         }
     }
 
+    /// Returns true if the observatory floats freely instead of sitting on the surface of its body
+    #[must_use]
+    pub fn is_free(&self) -> bool {
+        matches!(self.mount, Mount::Free { .. })
+    }
+
+    /// Sets whether observations are corrected for stellar aberration
+    pub fn set_aberration(&mut self, aberration: bool) {
+        self.aberration = aberration;
+    }
+
+    /// Returns true if observations are corrected for stellar aberration
+    #[must_use]
+    pub fn has_aberration(&self) -> bool {
+        self.aberration
+    }
+
+    /// Sets whether observations are corrected for the time light takes to reach the observatory
+    pub fn set_light_time(&mut self, light_time: bool) {
+        self.light_time = light_time;
+    }
+
+    /// Returns true if observations are corrected for the time light takes to reach the
+    /// observatory
+    #[must_use]
+    pub fn has_light_time(&self) -> bool {
+        self.light_time
+    }
+
     /// Takes bodies from a universal coordinate space and converts them to local coordinates
     /// relative to the observatory
     #[must_use]
     pub fn observe(&self, time: Float) -> Vec<LocalObservation> {
+        self.observe_with_light_time(time, self.light_time)
+    }
+
+    /// Same as [`Self::observe`], but overrides whether observations are corrected for
+    /// light-travel time
+    #[must_use]
+    pub fn observe_with_light_time(&self, time: Float, light_time: bool) -> Vec<LocalObservation> {
+        match &self.mount {
+            Mount::Surface(location) => self.observe_from_surface(*location, time, light_time),
+            Mount::Free { offset, pointing } => {
+                self.observe_from_space(*offset, pointing, time, light_time)
+            }
+        }
+    }
+
+    /// Observes bodies above the horizon from the surface at `location`
+    fn observe_from_surface(
+        &self,
+        location: Quaternion<Float>,
+        time: Float,
+        light_time: bool,
+    ) -> Vec<LocalObservation> {
+        self.locate_from_surface(location, time, light_time)
+            .into_iter()
+            .filter_map(|(body, local_coordinates, horizon)| {
+                // Filter out bodies below the horizon, or hidden by terrain
+                (atmosphere::to_altitude(local_coordinates.polar_angle) >= horizon)
+                    .then_some((body, local_coordinates))
+            })
+            .collect()
+    }
+
+    /// Gets the apparent location of every body from the surface at `location`, along with the
+    /// altitude (in radians) of the horizon in the direction of the body
+    fn locate_from_surface(
+        &self,
+        location: Quaternion<Float>,
+        time: Float,
+        light_time: bool,
+    ) -> Vec<(Arc, Spherical<Float>, Float)> {
         if let Ok(body) = self.body.read() {
-            let raw_observations = body.get_observations_from_here(time);
+            let raw_observations = if light_time || self.aberration {
+                body.get_apparent_observations_from_here(time, light_time, self.aberration)
+            } else {
+                body.get_observations_from_here(time)
+            };
+            // The observatory is above the surface, directly "up" from the centre of the body
+            let radius = body.radius.unwrap_or(0.0);
+            let surface = Vector3::UP * (radius + self.elevation);
+            let body_atmosphere = body
+                .atmosphere
+                .map(|atmosphere| atmosphere.at_elevation(self.elevation));
+            let dip = self.get_horizon_dip(body.radius);
 
             // Rotate observations to put them in the local coordinate space from equatorial coordinate
             // space
             raw_observations
                 .iter()
-                .filter_map(|(body, pos)| {
-                    let local_coordinates =
-                        Vector3::from(quaternion::rotate_vector(self.location, (*pos).into()));
-                    // FIXME: adjust z based on the body's radius since we aren't observing from the
-                    // center of the body
-
-                    // Filter out bodies below the horizon
-                    if local_coordinates.z >= 0.0 {
-                        Some((body.clone(), local_coordinates.into()))
-                    } else {
-                        None
+                .map(|(body, pos)| {
+                    let mut local_coordinates: Spherical<Float> =
+                        (Vector3::from(quaternion::rotate_vector(location, (*pos).into()))
+                            - surface)
+                            .into();
+
+                    // Bodies appear higher in the sky than they are when seen through air
+                    if let Some(atmosphere) = &body_atmosphere {
+                        local_coordinates.polar_angle -= atmosphere
+                            .get_refraction(atmosphere::to_altitude(local_coordinates.polar_angle));
                     }
+
+                    let horizon = self.horizon.as_ref().map_or(-dip, |horizon| {
+                        horizon
+                            .get_altitude(local_coordinates.azimuthal_angle)
+                            .max(-dip)
+                    });
+                    (body.clone(), local_coordinates, horizon)
                 })
                 .collect()
         } else {
@@ -74,22 +279,281 @@ impl Observatory {
         }
     }
 
+    /// Finds when the `target` body rises, sets, and transits between `start` and `end` (in
+    /// hours), using the same horizon, refraction, and corrections as
+    /// [`Self::observe_with_light_time`] with the given `light_time`. The sky is sampled every `step` hours and events are refined between samples, so steps should
+    /// be well under half a day of the body the observatory is on. Rises and sets are when the
+    /// centre of the target crosses the horizon. Transits are only found where the altitude
+    /// changes, so a target that stays still in the sky never transits, and transits exactly at
+    /// `start` or `end` aren't reported.
+    ///
+    /// Returns `None` for free-floating observatories (which have no horizon), or if the target
+    /// can't be seen from the observatory, e.g. it is the body the observatory is on. Also
+    /// returns `None` if `step` isn't positive or the range is empty.
+    #[must_use]
+    pub fn get_almanac(
+        &self,
+        target: &Arc,
+        start: Float,
+        end: Float,
+        step: Float,
+        light_time: bool,
+    ) -> Option<Almanac> {
+        /// Unit: hours
+        ///
+        /// Definition: How precisely the times of events are found
+        const TOLERANCE: Float = 1e-4;
+        /// Unit: radians
+        ///
+        /// Definition: Smallest change in altitude around a transit, so the rounding errors of a
+        /// target that stays still aren't found as transits
+        const FLAT: Float = 1e-6;
+
+        let Mount::Surface(location) = self.mount else {
+            return None;
+        };
+        if step <= 0.0 || start >= end {
+            return None;
+        }
+        // The apparent location, and height above the horizon
+        let locate = |time| {
+            self.locate_from_surface(location, time, light_time)
+                .into_iter()
+                .find(|(body, _, _)| std::sync::Arc::ptr_eq(body, target))
+                .map(|(_, local_coordinates, horizon)| {
+                    let altitude = atmosphere::to_altitude(local_coordinates.polar_angle);
+                    (local_coordinates, altitude - horizon)
+                })
+        };
+        let height = |time| locate(time).map_or(Float::NAN, |(_, height)| height);
+        let altitude = |time| {
+            locate(time).map_or(Float::NAN, |(local_coordinates, _)| {
+                atmosphere::to_altitude(local_coordinates.polar_angle)
+            })
+        };
+        let to_event = |kind, time| {
+            let (local_coordinates, _) = locate(time)?;
+            Some(AlmanacEvent {
+                kind,
+                time,
+                altitude: atmosphere::to_altitude(local_coordinates.polar_angle),
+                azimuth: local_coordinates.azimuthal_angle,
+            })
+        };
+
+        let mut times = vec![start];
+        while let Some(time) = times
+            .last()
+            .map(|time| time + step)
+            .filter(|time| *time < end)
+        {
+            times.push(time);
+        }
+        times.push(end);
+        let samples: Vec<Float> = times
+            .iter()
+            .map(|time| {
+                locate(*time).map(|(local_coordinates, _)| {
+                    atmosphere::to_altitude(local_coordinates.polar_angle)
+                })
+            })
+            .collect::<Option<_>>()?;
+
+        // Transits are where the altitude peaks or dips between samples. The first and last
+        // samples only have one neighbour, so may have a transit between them and the neighbour
+        let mut events = Vec::new();
+        for i in 0..samples.len() {
+            let (previous, next) = (i.saturating_sub(1), (i + 1).min(samples.len() - 1));
+            let around = &samples[previous..=next];
+            let (lowest, highest) = around.iter().fold(
+                (Float::INFINITY, Float::NEG_INFINITY),
+                |(low, high), sample| (low.min(*sample), high.max(*sample)),
+            );
+            if highest - lowest < FLAT {
+                continue;
+            }
+
+            let (previous, next) = (times[previous], times[next]);
+            let (kind, time) = if samples[i] >= highest {
+                let time = event::find_minimum(|t| -altitude(t), previous, next, TOLERANCE);
+                (AlmanacEventKind::UpperTransit, time)
+            } else if samples[i] <= lowest {
+                let time = event::find_minimum(altitude, previous, next, TOLERANCE);
+                (AlmanacEventKind::LowerTransit, time)
+            } else {
+                continue;
+            };
+            // Around the ends the altitude may just keep rising or falling past the range
+            if time <= start + 2.0 * TOLERANCE || time >= end - 2.0 * TOLERANCE {
+                continue;
+            }
+
+            // Flat samples can find the same transit twice
+            let repeated = events.last().is_some_and(|last: &AlmanacEvent| {
+                last.kind == kind && (last.time - time).abs() < step / 2.0
+            });
+            if !repeated {
+                events.extend(to_event(kind, time));
+            }
+        }
+
+        // Rises and sets are where the height changes sign, checking around transits to find
+        // brief rises or sets between samples
+        times.extend(events.iter().map(|event| event.time));
+        times.sort_by(Float::total_cmp);
+        let mut previous = (times[0], height(times[0]));
+        let visibility = if previous.1 >= 0.0 {
+            Visibility::AlwaysAbove
+        } else {
+            Visibility::AlwaysBelow
+        };
+        let mut crossings = Vec::new();
+        for time in &times[1..] {
+            let current = (*time, height(*time));
+            if (previous.1 >= 0.0) != (current.1 >= 0.0) {
+                let kind = if current.1 >= 0.0 {
+                    AlmanacEventKind::Rise
+                } else {
+                    AlmanacEventKind::Set
+                };
+                let time = event::find_root(height, previous.0, current.0, TOLERANCE);
+                crossings.extend(to_event(kind, time));
+            }
+            previous = current;
+        }
+
+        let visibility = if crossings.is_empty() {
+            visibility
+        } else {
+            Visibility::RisesAndSets
+        };
+        events.extend(crossings);
+        events.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Some(Almanac { events, visibility })
+    }
+
+    /// Observes every body from `offset` away from the body, centred on the direction of
+    /// `pointing`
+    fn observe_from_space(
+        &self,
+        offset: Vector3<Float>,
+        pointing: &Pointing,
+        time: Float,
+        light_time: bool,
+    ) -> Vec<LocalObservation> {
+        let mut observations = if let Ok(body) = self.body.read() {
+            body.get_inertial_observations_from_here(time, light_time, self.aberration)
+        } else {
+            warn!("The body was poisoned, could not make observations from it");
+            return vec![];
+        };
+        // The body isn't in the way, so it can be seen too
+        observations.push((self.body.clone(), Vector3::ORIGIN));
+
+        let direction: Vector3<Float> = match pointing {
+            Pointing::Equatorial {
+                right_ascension,
+                declination,
+            } => Spherical {
+                radius: 1.0,
+                polar_angle: float::FRAC_PI_2 - declination,
+                azimuthal_angle: *right_ascension,
+            }
+            .into(),
+            Pointing::LookAt(target) => observations
+                .iter()
+                .find(|(body, _)| std::sync::Arc::ptr_eq(body, target))
+                .map_or_else(
+                    || {
+                        warn!("The observatory is looking at a body that can't be observed");
+                        Vector3::UP
+                    },
+                    |(_, location)| *location - offset,
+                ),
+        };
+        let rotation = quaternion::rotation_from_to(direction.into(), Vector3::UP.into());
+
+        observations
+            .into_iter()
+            .map(|(body, location)| {
+                let local_coordinates: Vector3<Float> =
+                    quaternion::rotate_vector(rotation, (location - offset).into()).into();
+                (body, local_coordinates.into())
+            })
+            .collect()
+    }
+
+    /// Generates a copy of this observatory on the matching body (by ID) in another tree with the
+    /// same structure, e.g. a copy from [`super::Body::deep_clone`]
+    #[must_use]
+    pub fn with_root(&self, root: &Arc) -> Self {
+        let rebind = |body: &Arc| body.read().map(|b| get_body(root, &b.get_id())).ok();
+
+        let mut result = self.clone();
+        if let Some(body) = rebind(&self.body) {
+            result.body = body;
+        }
+        if let Mount::Free {
+            pointing: Pointing::LookAt(target),
+            ..
+        } = &mut result.mount
+        {
+            if let Some(new) = rebind(target) {
+                *target = new;
+            }
+        }
+        result
+    }
+
+    /// Gets the fraction of light from an observed body at `location` (from [`Self::observe`])
+    /// that makes it through the atmosphere of the body the observatory is on
+    #[must_use]
+    pub fn get_transmission(&self, location: &Spherical<Float>) -> Float {
+        if self.is_free() {
+            return 1.0;
+        }
+        self.body
+            .read()
+            .ok()
+            .and_then(|body| body.atmosphere)
+            .map_or(1.0, |atmosphere| {
+                atmosphere
+                    .at_elevation(self.elevation)
+                    .get_transmission(atmosphere::to_altitude(location.polar_angle))
+            })
+    }
+
     #[must_use]
     pub fn get_name(&self) -> String {
-        let lat_long = Spherical::from(Vector3::from(quaternion::rotate_vector(
-            self.location,
-            Vector3::UP.into(),
-        )));
-        self.name.clone().unwrap_or_else(|id| {
-            format!(
-                "{}@{:.2}N{:.2}E",
+        self.name.clone().unwrap_or_else(|id| match self.mount {
+            Mount::Surface(location) => {
+                let lat_long = Spherical::from(Vector3::from(quaternion::rotate_vector(
+                    location,
+                    Vector3::UP.into(),
+                )));
+                format!(
+                    "{}@{:.2}N{:.2}E",
+                    to_name(&id),
+                    lat_long.polar_angle.to_degrees() - 90.0,
+                    lat_long.azimuthal_angle.to_degrees() - 180.0
+                )
+            }
+            Mount::Free { offset, .. } => format!(
+                "{}@free{:.2}x{:.2}y{:.2}z",
                 to_name(&id),
-                lat_long.polar_angle.to_degrees() - 90.0,
-                lat_long.azimuthal_angle.to_degrees() - 180.0
-            )
+                offset.x,
+                offset.y,
+                offset.z
+            ),
         })
     }
 
+    /// Gets the body the observatory is on (or floating near)
+    #[must_use]
+    pub fn get_body(&self) -> &Arc {
+        &self.body
+    }
+
     #[must_use]
     pub fn constellations(&self) -> &Vec<crate::constellation::Constellation> {
         &self.constellations
@@ -111,8 +575,9 @@ impl Observatory {
 #[allow(clippy::module_name_repetitions)]
 #[serde(rename_all = "camelCase")]
 pub struct WeakObservatory {
-    /// Latitude and longitude of the observatory
-    location: Spherical<Float>,
+    /// Where the observatory is relative to the body
+    #[serde(flatten)]
+    mount: WeakMount,
     /// ID of the body that this observatory is on
     body_id: Vec<usize>,
     /// The user defined name. If none it is treated as a flag to generate a name based on the body
@@ -125,35 +590,137 @@ pub struct WeakObservatory {
     /// [Modern](https://en.wikipedia.org/wiki/IAU_designated_constellations))
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     constellations: Vec<crate::constellation::weak::Weak>,
+
+    /// Correct observations for light-travel time
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    light_time: bool,
+
+    /// Correct observations for stellar aberration
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    aberration: bool,
+
+    /// Outline of the terrain around the observatory
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    horizon: Option<HorizonMask>,
+
+    /// Height above the surface of the body in light seconds
+    #[serde(skip_serializing_if = "is_zero", default)]
+    elevation: Float,
 }
 
-/// Converts a [`WeakObservatory`] to a regular [`Observatory`] by adding back reference counted
-/// variables correctly.
+/// Returns true if a value is zero, used to skip serializing optional values
+fn is_zero(value: &Float) -> bool {
+    *value == 0.0
+}
+
+/// Serialized form of [`Mount`]
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum WeakMount {
+    /// Floating freely near the body
+    Free {
+        /// Offset and pointing of the observatory
+        free: WeakFreeMount,
+    },
+    /// On the surface of the body
+    Surface {
+        /// Latitude and longitude of the observatory
+        location: Spherical<Float>,
+    },
+}
+
+/// Serialized form of [`Mount::Free`]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WeakFreeMount {
+    /// Offset from the centre of the body in light seconds
+    #[serde(default = "origin")]
+    offset: Vector3<Float>,
+    /// Which way the observatory faces
+    pointing: WeakPointing,
+}
+
+/// Returns the origin, used as the default offset of free observatories
+fn origin() -> Vector3<Float> {
+    Vector3::ORIGIN
+}
+
+/// Serialized form of [`Pointing`], with bodies referred to by ID
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", untagged)]
+enum WeakPointing {
+    /// A fixed direction
+    #[serde(rename_all = "camelCase")]
+    Equatorial {
+        /// Angle around the z axis from the x axis, in radians
+        right_ascension: Float,
+        /// Angle above the xy plane, in radians
+        declination: Float,
+    },
+    /// Faces a body
+    #[serde(rename_all = "camelCase")]
+    LookAt {
+        /// ID of the body to face
+        look_at: Vec<usize>,
+    },
+}
+
+/// Gets the body with the given ID
 ///
 /// # Panics
 ///
-/// Panics if a body in the tree has a poisoned lock
-pub fn to_observatory(weak_observatory: WeakObservatory, root: &Arc) -> Observatory {
+/// Panics if a body in the tree has a poisoned lock, or the ID is not in the tree
+fn get_body(root: &Arc, id: &[usize]) -> Arc {
     let mut body = root.clone();
-    for child_id in &weak_observatory.body_id {
+    for child_id in id {
         // HACK: remove unwrap here, probably by returning an Option<Observatory>
         let b = body.read().unwrap().children[*child_id].clone();
         body = b;
     }
-    Observatory::new(
-        weak_observatory.location,
-        body.clone(),
-        weak_observatory.name.ok_or(weak_observatory.body_id),
-        weak_observatory
-            .constellations
-            .into_iter()
-            .map(|weak| weak.upgrade(root))
-            .collect(),
-    )
+    body
+}
+
+/// Converts a [`WeakObservatory`] to a regular [`Observatory`] by adding back reference counted
+/// variables correctly.
+///
+/// # Panics
+///
+/// Panics if a body in the tree has a poisoned lock
+pub fn to_observatory(weak_observatory: WeakObservatory, root: &Arc) -> Observatory {
+    let body = get_body(root, &weak_observatory.body_id);
+    let name = weak_observatory.name.ok_or(weak_observatory.body_id);
+    let constellations = weak_observatory
+        .constellations
+        .into_iter()
+        .map(|weak| weak.upgrade(root))
+        .collect();
+    let mut observatory = match weak_observatory.mount {
+        WeakMount::Surface { location } => Observatory::new(location, body, name, constellations),
+        WeakMount::Free {
+            free: WeakFreeMount { offset, pointing },
+        } => {
+            let pointing = match pointing {
+                WeakPointing::Equatorial {
+                    right_ascension,
+                    declination,
+                } => Pointing::Equatorial {
+                    right_ascension,
+                    declination,
+                },
+                WeakPointing::LookAt { look_at } => Pointing::LookAt(get_body(root, &look_at)),
+            };
+            Observatory::new_free(offset, pointing, body, name, constellations)
+        }
+    };
+    observatory.set_light_time(weak_observatory.light_time);
+    observatory.set_aberration(weak_observatory.aberration);
+    observatory.set_horizon(weak_observatory.horizon);
+    observatory.set_elevation(weak_observatory.elevation);
+    observatory
 }
 
 /// Converts a ID to a string of dash ("-") separated values that is adequite for generating names
-pub(super) fn to_name(id: &[usize]) -> String {
+pub(crate) fn to_name(id: &[usize]) -> String {
     if id.is_empty() {
         String::new()
     } else {
@@ -171,16 +738,33 @@ pub(super) fn to_name(id: &[usize]) -> String {
 
 impl From<Observatory> for WeakObservatory {
     fn from(value: Observatory) -> Self {
-        let (qw, [qx, qy, qz]) = value.location;
-        // From https://www.euclideanspace.com/maths/geometry/rotations/conversions/quaternionToEuler/
-
-        // asin(2*qx*qy + 2*qz*qw)
-        // We take acos because we are using a polar angle, not a latitude
-        let polar_angle = (2.0 - qx * qy + 2.0 * qz * qw).acos();
-
-        // atan2(2*qy*qw-2*qx*qz , 1 - 2*qy^2 - 2*qz^2)
-        let azimuthal_angle =
-            (2.0 * qy * qw - 2.0 * qx * qz).atan2(1.0 - 2.0 * qy * qy - 2.0 * qz * qz);
+        let mount = match value.mount {
+            Mount::Surface((w, [x, y, z])) => WeakMount::Surface {
+                // The inverse rotation takes the north pole back to the location
+                location: Vector3::from(quaternion::rotate_vector(
+                    (w, [-x, -y, -z]),
+                    Vector3::UP.into(),
+                ))
+                .into(),
+            },
+            Mount::Free { offset, pointing } => WeakMount::Free {
+                free: WeakFreeMount {
+                    offset,
+                    pointing: match pointing {
+                        Pointing::Equatorial {
+                            right_ascension,
+                            declination,
+                        } => WeakPointing::Equatorial {
+                            right_ascension,
+                            declination,
+                        },
+                        Pointing::LookAt(target) => WeakPointing::LookAt {
+                            look_at: target.read().map(|body| body.get_id()).unwrap_or_default(),
+                        },
+                    },
+                },
+            },
+        };
 
         let body_id = value
             .body
@@ -188,11 +772,7 @@ impl From<Observatory> for WeakObservatory {
             .map(|body| body.get_id())
             .unwrap_or_default();
         WeakObservatory {
-            location: Spherical {
-                polar_angle,
-                azimuthal_angle,
-                radius: 1.0,
-            },
+            mount,
             body_id,
             name: None,
             constellations: value
@@ -200,6 +780,10 @@ impl From<Observatory> for WeakObservatory {
                 .into_iter()
                 .map(crate::constellation::weak::Weak::from)
                 .collect(),
+            light_time: value.light_time,
+            aberration: value.aberration,
+            horizon: value.horizon,
+            elevation: value.elevation,
         }
     }
 }
@@ -209,8 +793,15 @@ mod tests {
     use coordinates::prelude::{Spherical, ThreeDimensionalConsts, Vector3};
 
     use crate::{
-        body::{observatory::WeakObservatory, rotating::Rotating, Arc, Body},
+        body::{
+            atmosphere::Atmosphere,
+            observatory::{Observatory, WeakObservatory},
+            rotating::Rotating,
+            Arc, Body,
+        },
+        consts::float,
         dynamic::fixed::Fixed,
+        Float,
     };
 
     #[allow(dead_code)] // Will be useful if we rewrite that old test
@@ -234,4 +825,468 @@ mod tests {
 
         assert_eq!(observatories.len(), 6);
     }
+
+    #[test]
+    fn topocentric_parallax() {
+        const RADIUS: Float = 1.0;
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        planet.write().unwrap().rotation = Some(Rotating::new(24.0, Spherical::UP));
+        planet.write().unwrap().radius = Some(RADIUS);
+        // Overhead, and just above the horizon when viewed from the centre of the planet
+        let _ = Body::new(Some(planet.clone()), Fixed::new(Vector3::RIGHT * 10.0));
+        let _ = Body::new(
+            Some(planet.clone()),
+            Fixed::new(Vector3::from((0.5, 10.0, 0.0))),
+        );
+
+        // On the equator, facing the positive x axis
+        let observatory = Observatory::new(
+            Spherical::new(1.0, float::FRAC_PI_2, 0.0),
+            planet,
+            Err(vec![]),
+            vec![],
+        );
+        let observations = observatory.observe(0.0);
+
+        // The overhead body is closer by the radius, and the other body has set
+        assert_eq!(observations.len(), 1);
+        let overhead = observations[0].1;
+        assert_float_absolute_eq!(overhead.radius, 10.0 - RADIUS, 1e-5);
+        assert_float_absolute_eq!(overhead.polar_angle, 0.0, 1e-3);
+    }
+
+    #[test]
+    fn refraction_lifts_bodies_above_horizon() {
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        planet.write().unwrap().rotation = Some(Rotating::new(24.0, Spherical::UP));
+        // A quarter of a degree below the horizon
+        let below = (0.25 as Float).to_radians().tan() * 10.0;
+        let _ = Body::new(
+            Some(planet.clone()),
+            Fixed::new(Vector3::from((-below, 10.0, 0.0))),
+        );
+        let observatory = Observatory::new(
+            Spherical::new(1.0, float::FRAC_PI_2, 0.0),
+            planet.clone(),
+            Err(vec![]),
+            vec![],
+        );
+
+        // No air, no refraction
+        assert!(observatory.observe(0.0).is_empty());
+        assert_float_absolute_eq!(observatory.get_transmission(&Spherical::UP), 1.0);
+
+        planet
+            .write()
+            .unwrap()
+            .set_atmosphere(Some(Atmosphere::default()));
+        let observations = observatory.observe(0.0);
+        assert_eq!(observations.len(), 1);
+        let altitude = float::FRAC_PI_2 - observations[0].1.polar_angle;
+        assert_float_absolute_eq!(altitude.to_degrees(), 0.25, 0.1);
+
+        // Light from near the horizon is heavily dimmed
+        assert!(observatory.get_transmission(&observations[0].1) < 0.01);
+    }
+
+    #[test]
+    fn correction_toggles() {
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let json = r#"{"location":{"r":1.0,"theta":0.0,"phi":0.0},"bodyId":[],"lightTime":true,"aberration":true}"#;
+        let weak: WeakObservatory = serde_json::from_str(json).unwrap();
+        let observatory = super::to_observatory(weak, &planet);
+        assert!(observatory.has_light_time());
+        assert!(observatory.has_aberration());
+
+        // Off by default
+        let json = r#"{"location":{"r":1.0,"theta":0.0,"phi":0.0},"bodyId":[]}"#;
+        let weak: WeakObservatory = serde_json::from_str(json).unwrap();
+        let observatory = super::to_observatory(weak, &planet);
+        assert!(!observatory.has_light_time() && !observatory.has_aberration());
+    }
+
+    #[test]
+    fn horizon_hides_bodies() {
+        use crate::body::horizon::HorizonMask;
+
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        planet.write().unwrap().rotation = Some(Rotating::new(24.0, Spherical::UP));
+        // 0.2 radians above the horizon in two opposite directions
+        let height = (0.2 as Float).tan() * 10.0;
+        let _ = Body::new(
+            Some(planet.clone()),
+            Fixed::new(Vector3::from((height, 10.0, 0.0))),
+        );
+        let _ = Body::new(
+            Some(planet.clone()),
+            Fixed::new(Vector3::from((height, -10.0, 0.0))),
+        );
+        Body::hydrate_all(&planet, &None);
+        let mut observatory = Observatory::new(
+            Spherical::new(1.0, float::FRAC_PI_2, 0.0),
+            planet,
+            Err(vec![]),
+            vec![],
+        );
+        let observations = observatory.observe(0.0);
+        assert_eq!(observations.len(), 2);
+
+        // A hill in the direction of the first body
+        let hill_azimuth = observations[0].1.azimuthal_angle;
+        observatory.set_horizon(Some(
+            HorizonMask::new(vec![
+                (hill_azimuth - 0.5, 0.0),
+                (hill_azimuth, 0.3),
+                (hill_azimuth + 0.5, 0.0),
+            ])
+            .unwrap(),
+        ));
+        let observations = observatory.observe(0.0);
+        assert_eq!(observations.len(), 1);
+        assert!((observations[0].1.azimuthal_angle - hill_azimuth).abs() > 1.0);
+    }
+
+    #[test]
+    fn elevation() {
+        const RADIUS: Float = 1.0;
+        const ELEVATION: Float = 0.1;
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        planet.write().unwrap().rotation = Some(Rotating::new(24.0, Spherical::UP));
+        planet.write().unwrap().radius = Some(RADIUS);
+        // Overhead, and a little below the mathematical horizon of the observatory
+        let _ = Body::new(Some(planet.clone()), Fixed::new(Vector3::RIGHT * 10.0));
+        let _ = Body::new(
+            Some(planet.clone()),
+            Fixed::new(Vector3::from((RADIUS - 0.1, 10.0, 0.0))),
+        );
+        Body::hydrate_all(&planet, &None);
+
+        let json = r#"{"location":{"r":1.0,"theta":1.5707963,"phi":0.0},"bodyId":[]}"#;
+        let weak: WeakObservatory = serde_json::from_str(json).unwrap();
+        let mut observatory = super::to_observatory(weak, &planet);
+        assert_float_absolute_eq!(observatory.get_elevation(), 0.0);
+        assert_eq!(observatory.observe(0.0).len(), 1);
+
+        // Higher up the overhead body is closer, and the horizon dips far enough to see the other
+        observatory.set_elevation(ELEVATION);
+        let observations = observatory.observe(0.0);
+        assert_eq!(observations.len(), 2);
+        let overhead = observations
+            .iter()
+            .find(|(_, location)| location.polar_angle < 0.1)
+            .unwrap();
+        assert_float_absolute_eq!(overhead.1.radius, 10.0 - RADIUS - ELEVATION, 1e-4);
+
+        let weak = WeakObservatory::from(observatory);
+        assert!(serde_json::to_string(&weak)
+            .unwrap()
+            .contains(r#""elevation":0.1"#));
+    }
+
+    #[test]
+    fn elevation_without_radius() {
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        planet.write().unwrap().rotation = Some(Rotating::new(24.0, Spherical::UP));
+        // Overhead, and a little below the mathematical horizon of the observatory
+        let _ = Body::new(Some(planet.clone()), Fixed::new(Vector3::RIGHT * 10.0));
+        let _ = Body::new(
+            Some(planet.clone()),
+            Fixed::new(Vector3::from((0.0, 10.0, 0.0))),
+        );
+        Body::hydrate_all(&planet, &None);
+
+        let json =
+            r#"{"location":{"r":1.0,"theta":1.5707963,"phi":0.0},"bodyId":[],"elevation":0.1}"#;
+        let weak: WeakObservatory = serde_json::from_str(json).unwrap();
+        let observatory = super::to_observatory(weak, &planet);
+
+        // Without a radius there is no curved surface, so the horizon doesn't dip to show the
+        // body half the sky below it
+        assert_eq!(observatory.observe(0.0).len(), 1);
+    }
+
+    #[test]
+    fn free_observatory() {
+        use super::Pointing;
+
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        // Spinning quickly, which shouldn't affect a free observatory
+        planet.write().unwrap().rotation = Some(Rotating::new(0.1, Spherical::UP));
+        planet.write().unwrap().radius = Some(1.0);
+        let moon = Body::new(Some(planet.clone()), Fixed::new(Vector3::RIGHT * 10.0));
+        Body::hydrate_all(&planet, &None);
+
+        let offset = Vector3::UP * 2.0;
+        let observatory = Observatory::new_free(
+            offset,
+            Pointing::LookAt(moon),
+            planet.clone(),
+            Err(vec![]),
+            vec![],
+        );
+        assert!(observatory.is_free());
+        for time in [0.0, 0.03, 0.07] {
+            // Both the moon and the planet it floats above can be seen
+            let observations = observatory.observe(time);
+            assert_eq!(observations.len(), 2);
+            let moon = observations
+                .iter()
+                .find(|(body, _)| !std::sync::Arc::ptr_eq(body, &planet))
+                .unwrap();
+            assert_float_absolute_eq!(moon.1.polar_angle, 0.0, 1e-3);
+            assert_float_absolute_eq!(moon.1.radius, (104.0 as Float).sqrt(), 1e-4);
+        }
+
+        // Facing straight down the z axis puts the planet at the centre
+        let observatory = Observatory::new_free(
+            offset,
+            Pointing::Equatorial {
+                right_ascension: 0.0,
+                declination: -float::FRAC_PI_2,
+            },
+            planet.clone(),
+            Err(vec![]),
+            vec![],
+        );
+        let observations = observatory.observe(0.0);
+        let centre = observations
+            .iter()
+            .find(|(body, _)| std::sync::Arc::ptr_eq(body, &planet))
+            .unwrap();
+        assert_float_absolute_eq!(centre.1.polar_angle, 0.0, 1e-3);
+        assert_float_absolute_eq!(centre.1.radius, 2.0, 1e-5);
+    }
+
+    #[test]
+    fn free_observatory_serialization() {
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let _ = Body::new(Some(planet.clone()), Fixed::new(Vector3::RIGHT * 10.0));
+        Body::hydrate_all(&planet, &None);
+
+        let json = r#"[
+            {"free":{"offset":{"x":0.0,"y":0.0,"z":2.0},"pointing":{"lookAt":[0]}},"bodyId":[]},
+            {"free":{"pointing":{"rightAscension":1.0,"declination":0.5}},"bodyId":[0]},
+            {"location":{"r":1.0,"theta":0.0,"phi":0.0},"bodyId":[]}
+        ]"#;
+        let weak: Vec<WeakObservatory> = serde_json::from_str(json).unwrap();
+        let observatories: Vec<Observatory> = weak
+            .into_iter()
+            .map(|weak| super::to_observatory(weak, &planet))
+            .collect();
+        assert!(observatories[0].is_free() && observatories[1].is_free());
+        assert!(!observatories[2].is_free());
+
+        for observatory in observatories {
+            let json = serde_json::to_string(&WeakObservatory::from(observatory.clone())).unwrap();
+            let weak: WeakObservatory = serde_json::from_str(&json).unwrap();
+            let new = super::to_observatory(weak, &planet);
+            assert_eq!(new.is_free(), observatory.is_free());
+            assert_eq!(new.observe(0.0).len(), observatory.observe(0.0).len());
+        }
+    }
+
+    #[test]
+    fn almanac() {
+        use crate::body::{
+            almanac::{AlmanacEventKind, Visibility},
+            horizon::HorizonMask,
+        };
+
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        planet.write().unwrap().rotation = Some(Rotating::new(24.0, Spherical::UP));
+        // Underfoot at the start
+        let target = Body::new(Some(planet.clone()), Fixed::new(Vector3::RIGHT * -10.0));
+        Body::hydrate_all(&planet, &None);
+        let mut observatory = Observatory::new(
+            Spherical::new(1.0, float::FRAC_PI_2, 0.0),
+            planet.clone(),
+            Err(vec![]),
+            vec![],
+        );
+
+        let almanac = observatory
+            .get_almanac(&target, 1.0, 25.0, 1.0, false)
+            .unwrap();
+        assert_eq!(almanac.visibility, Visibility::RisesAndSets);
+        let kinds: Vec<AlmanacEventKind> = almanac.events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                AlmanacEventKind::Rise,
+                AlmanacEventKind::UpperTransit,
+                AlmanacEventKind::Set,
+                AlmanacEventKind::LowerTransit
+            ]
+        );
+        let times: Vec<Float> = almanac.events.iter().map(|e| e.time).collect();
+        for (time, expected) in times.iter().zip([6.0, 12.0, 18.0, 24.0]) {
+            assert_float_absolute_eq!(time, expected, 1e-3);
+        }
+        assert_float_absolute_eq!(almanac.events[0].altitude, 0.0, 1e-3);
+        assert_float_absolute_eq!(almanac.events[1].altitude, float::FRAC_PI_2, 1e-2);
+
+        // Transits less than a step from the start or end of the range
+        let to_events = |almanac: super::Almanac| -> Vec<(AlmanacEventKind, Float)> {
+            almanac.events.iter().map(|e| (e.kind, e.time)).collect()
+        };
+        let events = to_events(
+            observatory
+                .get_almanac(&target, 11.8, 23.5, 1.0, false)
+                .unwrap(),
+        );
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].0, AlmanacEventKind::UpperTransit);
+        assert_float_absolute_eq!(events[0].1, 12.0, 1e-3);
+        assert_eq!(events[1].0, AlmanacEventKind::Set);
+        let events = to_events(
+            observatory
+                .get_almanac(&target, 0.5, 12.3, 1.0, false)
+                .unwrap(),
+        );
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].0, AlmanacEventKind::Rise);
+        assert_eq!(events[1].0, AlmanacEventKind::UpperTransit);
+        assert_float_absolute_eq!(events[1].1, 12.0, 1e-3);
+
+        // Hills delay the rise and hasten the set by the time it takes to turn over them
+        observatory.set_horizon(Some(HorizonMask::new(vec![(0.0, 0.1)]).unwrap()));
+        let almanac = observatory
+            .get_almanac(&target, 1.0, 25.0, 1.0, false)
+            .unwrap();
+        let delay = 0.1 * 24.0 / float::TAU;
+        assert_float_absolute_eq!(almanac.events[0].time, 6.0 + delay, 1e-3);
+        assert_float_absolute_eq!(almanac.events[2].time, 18.0 - delay, 1e-3);
+
+        // Can't see the planet from itself, or rises and sets from space
+        assert!(observatory
+            .get_almanac(&planet, 0.0, 24.0, 1.0, false)
+            .is_none());
+        let free = Observatory::new_free(
+            Vector3::ORIGIN,
+            super::Pointing::LookAt(target.clone()),
+            planet,
+            Err(vec![]),
+            vec![],
+        );
+        assert!(free.get_almanac(&target, 0.0, 24.0, 1.0, false).is_none());
+    }
+
+    #[test]
+    fn circumpolar_almanac() {
+        use crate::body::almanac::{AlmanacEventKind, Visibility};
+
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        planet.write().unwrap().rotation = Some(Rotating::new(24.0, Spherical::UP));
+        // Near the north and south celestial poles
+        let north = Body::new(
+            Some(planet.clone()),
+            Fixed::new(Vector3::from((1.0, 0.0, 10.0))),
+        );
+        let south = Body::new(
+            Some(planet.clone()),
+            Fixed::new(Vector3::from((1.0, 0.0, -10.0))),
+        );
+        Body::hydrate_all(&planet, &None);
+        // Far north
+        let observatory =
+            Observatory::new(Spherical::new(1.0, 0.3, 0.0), planet, Err(vec![]), vec![]);
+
+        let almanac = observatory
+            .get_almanac(&north, 1.0, 49.0, 1.0, false)
+            .unwrap();
+        assert_eq!(almanac.visibility, Visibility::AlwaysAbove);
+        // Still culminates once a day
+        let transits = almanac
+            .events
+            .iter()
+            .filter(|e| e.kind == AlmanacEventKind::UpperTransit)
+            .count();
+        assert_eq!(transits, 2);
+        assert!(almanac.events.iter().all(|e| e.altitude > 0.0));
+
+        let almanac = observatory
+            .get_almanac(&south, 0.0, 48.0, 1.0, false)
+            .unwrap();
+        assert_eq!(almanac.visibility, Visibility::AlwaysBelow);
+        assert!(!almanac
+            .events
+            .iter()
+            .any(|e| matches!(e.kind, AlmanacEventKind::Rise | AlmanacEventKind::Set)));
+    }
+
+    #[test]
+    fn still_almanac() {
+        use crate::body::almanac::Visibility;
+
+        // Without rotation the target never moves in the sky
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let target = Body::new(
+            Some(planet.clone()),
+            Fixed::new(Vector3::from((10.0, 3.0, 0.0))),
+        );
+        Body::hydrate_all(&planet, &None);
+        let observatory = Observatory::new(
+            Spherical::new(1.0, float::FRAC_PI_2, 0.0),
+            planet,
+            Err(vec![]),
+            vec![],
+        );
+
+        let almanac = observatory
+            .get_almanac(&target, 0.0, 48.0, 1.0, false)
+            .unwrap();
+        assert_eq!(almanac.visibility, Visibility::AlwaysAbove);
+        assert!(almanac.events.is_empty());
+    }
+
+    #[test]
+    fn light_time_almanac() {
+        use crate::{body::almanac::AlmanacEventKind, dynamic::circular::Circular};
+
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        // Turning in the plane of the orbit
+        planet.write().unwrap().rotation = Some(Rotating::new(
+            24.0,
+            Spherical::new(1.0, float::FRAC_PI_2, float::FRAC_PI_2),
+        ));
+        // An hour of light-travel time away, moving fast enough for it to matter
+        let target = Body::new(
+            Some(planet.clone()),
+            Circular::new_with_period(3600.0, 0.0, 0.0, 0.0, 240.0),
+        );
+        Body::hydrate_all(&planet, &None);
+        let observatory = Observatory::new(
+            Spherical::new(1.0, float::FRAC_PI_2, 0.0),
+            planet,
+            Err(vec![]),
+            vec![],
+        );
+
+        let rise = |light_time| {
+            observatory
+                .get_almanac(&target, 0.0, 48.0, 0.5, light_time)
+                .unwrap()
+                .events
+                .into_iter()
+                .find(|e| e.kind == AlmanacEventKind::Rise)
+                .unwrap()
+                .time
+        };
+        let (instant, delayed) = (rise(false), rise(true));
+        assert!((instant - delayed).abs() > 0.05);
+
+        // Matches the observations corrected for light-travel time
+        let super::Mount::Surface(surface) = observatory.mount else {
+            unreachable!()
+        };
+        let height = |time| {
+            let (_, location, horizon) = observatory
+                .locate_from_surface(surface, time, true)
+                .into_iter()
+                .find(|(body, _, _)| std::sync::Arc::ptr_eq(body, &target))
+                .unwrap();
+            float::FRAC_PI_2 - location.polar_angle - horizon
+        };
+        assert_float_absolute_eq!(height(delayed), 0.0, 1e-3);
+    }
 }
@@ -1,44 +1,241 @@
-use coordinates::prelude::{Spherical, ThreeDimensionalConsts, Vector3};
+use coordinates::{
+    prelude::{Spherical, ThreeDimensionalConsts, Vector3},
+    traits::{Cross3D, Magnitude},
+};
 use quaternion::Quaternion;
 use serde::{Deserialize, Serialize};
 
-use crate::{consts::float, EllipticObservation, Float};
+use crate::{
+    consts::float,
+    dynamic::{circular::Circular, keplerian::Keplerian, Dynamic},
+    EllipticObservation, Float,
+};
 
 /// A struct that defines the rotation of a body.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(from = "SerializableRotating", into = "SerializableRotating")]
+#[serde(try_from = "SerializableRotating", into = "SerializableRotating")]
 pub struct Rotating {
     /// The time for the body to rotate 360 degrees, as opposed to a [solar day](https://en.wikipedia.org/wiki/Synodic_day)
     sidereal_period: Float,
-    /// The direction of the geographic north pole.
+    /// The direction of the geographic north pole, or the centre of the cone the pole traces if
+    /// [`Self::precession`] is set.
     axis: Vector3<Float>,
+    /// How the north pole moves around [`Self::axis`] over time
+    precession: Option<AxialPrecession>,
+    /// Unit: radians
+    ///
+    /// Definition: Angle of the prime meridian around the axis at [`Self::epoch`], e.g. the
+    /// [sidereal time](https://en.wikipedia.org/wiki/Sidereal_time) at Greenwich for the earth.
+    phase: Float,
+    /// Unit: hours
+    ///
+    /// Definition: Time at which the prime meridian is at [`Self::phase`].
+    epoch: Float,
+    /// Unit: radians per hour squared
+    ///
+    /// Definition: Change in the rate of rotation over time, positive values speed the rotation
+    /// up.
+    rate_drift: Float,
+    /// Keeps the same face towards the parent body, see [`Self::lock_to_orbit`]
+    tidal_lock: Option<TidalLock>,
 }
 
+/// Rotation that matches the orbit around the parent body, i.e. [tidal
+/// locking](https://en.wikipedia.org/wiki/Tidal_locking). The period and axis of the rotation are
+/// taken from the orbit, and longitude 0 faces the parent body at t=0.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TidalLock {
+    /// Unit: radians
+    ///
+    /// Definition: Amplitude of the [physical libration](https://en.wikipedia.org/wiki/Libration)
+    /// in longitude, which peaks a quarter of an orbit after periapsis.
+    #[serde(default)]
+    libration: Float,
+    /// Unit: radians
+    ///
+    /// Definition: Mean anomaly of the orbit at t=0, used to keep the libration in step with the
+    /// orbit.
+    #[serde(skip)]
+    mean_anomaly_at_epoch: Float,
+    /// Unit: radians
+    ///
+    /// Definition: Angle added to the prime meridian so that longitude 0 faces the parent body
+    /// at t=0.
+    #[serde(skip)]
+    facing: Float,
+}
+
+/// The slow movement of the north pole of a body around a cone, i.e. [axial
+/// precession](https://en.wikipedia.org/wiki/Axial_precession), with optional
+/// [nutation](https://en.wikipedia.org/wiki/Nutation).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AxialPrecession {
+    /// Unit: radians
+    ///
+    /// Definition: Angle between the north pole and the centre of the cone, e.g. the
+    /// [obliquity](https://en.wikipedia.org/wiki/Axial_tilt) if the centre is the orbit normal.
+    cone_angle: Float,
+    /// Unit: hours
+    ///
+    /// Definition: Time for the north pole to complete one lap of the cone. Positive periods move
+    /// clockwise when viewed from above the cone (like the earth), negative periods move
+    /// counter-clockwise.
+    period: Float,
+    /// Unit: radians
+    ///
+    /// Definition: How far around the cone the north pole is at t=0.
+    #[serde(default)]
+    phase: Float,
+    /// Periodic wobble on top of the precession
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nutation: Option<Nutation>,
+}
+
+/// A periodic wobble in the cone angle and position around the cone of [`AxialPrecession`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Nutation {
+    /// Unit: radians
+    ///
+    /// Definition: Amplitude of the change in the cone angle, i.e. nutation in obliquity.
+    obliquity: Float,
+    /// Unit: radians
+    ///
+    /// Definition: Amplitude of the change in the position around the cone, i.e. nutation in
+    /// longitude.
+    longitude: Float,
+    /// Unit: hours
+    ///
+    /// Definition: Time for one complete wobble.
+    period: Float,
+    /// Unit: radians
+    ///
+    /// Definition: How far through the wobble the pole is at t=0.
+    #[serde(default)]
+    phase: Float,
+}
+
+impl AxialPrecession {
+    /// Creates a precession where the north pole is `cone_angle` radians from the centre of the
+    /// cone, and moves around the cone every `period` hours, starting `phase` radians around.
+    #[must_use]
+    pub fn new(cone_angle: Float, period: Float, phase: Float) -> Self {
+        Self {
+            cone_angle,
+            period,
+            phase,
+            nutation: None,
+        }
+    }
+
+    /// Adds nutation, with amplitudes `obliquity` and `longitude` (both in radians) that repeats
+    /// every `period` hours, starting `phase` radians through the wobble.
+    #[must_use]
+    pub fn with_nutation(
+        mut self,
+        obliquity: Float,
+        longitude: Float,
+        period: Float,
+        phase: Float,
+    ) -> Self {
+        self.nutation = Some(Nutation {
+            obliquity,
+            longitude,
+            period,
+            phase,
+        });
+        self
+    }
+
+    /// Gets the cone angle and the (clockwise) angle around the cone at the given time
+    fn get_angles(&self, time: Float) -> (Float, Float) {
+        let mut cone_angle = self.cone_angle;
+        let mut angle = time % self.period / self.period * float::TAU + self.phase;
+
+        if let Some(nutation) = &self.nutation {
+            let (sin, cos) =
+                (time % nutation.period / nutation.period * float::TAU + nutation.phase).sin_cos();
+            cone_angle += nutation.obliquity * cos;
+            angle += nutation.longitude * sin;
+        }
+
+        (cone_angle, angle)
+    }
+}
+
+/// Serialized form of [`Rotating`] that stores the axis as a spherical direction
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 struct SerializableRotating {
     /// Time taken for the body to rotate 360 degrees. Note: different from the [solar day](https://en.wikipedia.org/wiki/Synodic_day) which is
     /// the time taken for the parent body to reach the same meridian.
-    sidereal_period: Float,
-    /// Location of the true-north pole
-    axis: Spherical<Float>,
+    #[serde(default)]
+    sidereal_period: Option<Float>,
+    /// Location of the true-north pole, or the centre of the precession cone
+    #[serde(default)]
+    axis: Option<Spherical<Float>>,
+    /// Precession of the north pole
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    precession: Option<AxialPrecession>,
+    /// Locks the rotation to the orbit, in which case the period and axis are optional
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tidally_locked: Option<TidalLock>,
+    /// Angle of the prime meridian at the epoch
+    #[serde(default, skip_serializing_if = "is_zero")]
+    phase: Float,
+    /// Time when the prime meridian is at the phase
+    #[serde(default, skip_serializing_if = "is_zero")]
+    epoch: Float,
+    /// Change in the rate of rotation over time
+    #[serde(default, skip_serializing_if = "is_zero")]
+    rate_drift: Float,
+}
+
+/// Returns true if a value is zero, used to skip serializing optional values
+fn is_zero(value: &Float) -> bool {
+    *value == 0.0
 }
 
 impl From<Rotating> for SerializableRotating {
     fn from(value: Rotating) -> Self {
         SerializableRotating {
-            sidereal_period: value.sidereal_period,
-            axis: value.axis.into(),
+            sidereal_period: Some(value.sidereal_period),
+            axis: Some(value.axis.into()),
+            precession: value.precession,
+            tidally_locked: value.tidal_lock,
+            phase: value.phase,
+            epoch: value.epoch,
+            rate_drift: value.rate_drift,
         }
     }
 }
 
-impl From<SerializableRotating> for Rotating {
-    fn from(value: SerializableRotating) -> Self {
-        Rotating {
-            sidereal_period: value.sidereal_period,
-            axis: value.axis.into(),
-        }
+impl TryFrom<SerializableRotating> for Rotating {
+    type Error = &'static str;
+
+    fn try_from(value: SerializableRotating) -> Result<Self, Self::Error> {
+        // Tidally locked bodies get their period and axis from the orbit once the body is
+        // hydrated, so placeholders are fine until then
+        let (sidereal_period, axis) = match (value.sidereal_period, value.axis) {
+            (Some(period), Some(axis)) => (period, axis.into()),
+            (period, axis) if value.tidally_locked.is_some() => (
+                period.unwrap_or(Float::INFINITY),
+                axis.map_or(Vector3::UP, Into::into),
+            ),
+            _ => return Err("siderealPeriod and axis are required unless tidallyLocked is set"),
+        };
+
+        Ok(Rotating {
+            sidereal_period,
+            axis,
+            precession: value.precession,
+            tidal_lock: value.tidally_locked,
+            phase: value.phase,
+            epoch: value.epoch,
+            rate_drift: value.rate_drift,
+        })
     }
 }
 
@@ -53,19 +250,158 @@ impl Rotating {
         Self {
             sidereal_period,
             axis: axis.into(),
+            precession: None,
+            tidal_lock: None,
+            phase: 0.0,
+            epoch: 0.0,
+            rate_drift: 0.0,
         }
     }
 
-    /// Returns a rotation for a given time, should just adjust the longitude the observed body is
-    /// over not the latitude.
+    /// Starts the prime meridian `phase` radians around the axis at `epoch` hours, e.g. to match
+    /// the sidereal time of a real planet
+    #[must_use]
+    pub fn with_phase(mut self, phase: Float, epoch: Float) -> Self {
+        self.phase = phase;
+        self.epoch = epoch;
+        self
+    }
+
+    /// Speeds up the rotation by `rate_drift` radians per hour every hour since the epoch, negative
+    /// values slow it down (like the earth)
+    #[must_use]
+    pub fn with_rate_drift(mut self, rate_drift: Float) -> Self {
+        self.rate_drift = rate_drift;
+        self
+    }
+
+    /// Creates a rotation that keeps the same face towards the parent of a body following
+    /// `orbit`, wobbling back and forth by up to `libration` radians over each orbit.
+    ///
+    /// Returns [`None`] if the orbit is not a [`Keplerian`] or [`Circular`] orbit.
     #[must_use]
-    fn get_rotation(&self, time: Float) -> Quaternion<Float> {
-        quaternion::axis_angle(self.axis.into(), -self.get_mean_angle(time))
+    pub fn new_tidally_locked(orbit: &dyn Dynamic, libration: Float) -> Option<Self> {
+        let mut rotating = Self {
+            sidereal_period: Float::INFINITY,
+            axis: Vector3::UP,
+            precession: None,
+            tidal_lock: Some(TidalLock {
+                libration,
+                mean_anomaly_at_epoch: 0.0,
+                facing: 0.0,
+            }),
+            phase: 0.0,
+            epoch: 0.0,
+            rate_drift: 0.0,
+        };
+        rotating.lock_to_orbit(orbit).then_some(rotating)
     }
 
-    /// Gets angle relative to the reference direction since last complete revolution
+    /// Returns true if the rotation is locked to the orbit around the parent body
+    #[must_use]
+    pub fn is_tidally_locked(&self) -> bool {
+        self.tidal_lock.is_some()
+    }
+
+    /// Updates the period, axis, and prime meridian of a tidally locked rotation to match
+    /// `orbit`, so that longitude 0 faces the parent body at t=0 (before any libration or
+    /// [`Self::with_phase`]). Does nothing to rotations that are not tidally locked.
+    ///
+    /// Returns false if the rotation is tidally locked but the orbit is not a [`Keplerian`] or
+    /// [`Circular`] orbit.
+    pub fn lock_to_orbit(&mut self, orbit: &dyn Dynamic) -> bool {
+        if self.tidal_lock.is_none() {
+            return true;
+        }
+
+        let parent = -orbit.get_offset(0.0);
+        let orbit = orbit.as_any();
+        let (period, axis, mean_anomaly_at_epoch) =
+            if let Some(keplerian) = orbit.downcast_ref::<Keplerian>() {
+                (
+                    keplerian.get_orbital_period(),
+                    keplerian.get_orbit_normal(),
+                    keplerian.get_mean_anomaly(0.0),
+                )
+            } else if let Some(circular) = orbit.downcast_ref::<Circular>() {
+                (
+                    circular.get_orbital_period(),
+                    circular.get_orbit_normal(),
+                    circular.get_angle(0.0),
+                )
+            } else {
+                return false;
+            };
+
+        self.sidereal_period = period;
+        self.axis = axis;
+
+        // Azimuth of the parent in equatorial coordinates before turning, which the prime meridian
+        // is turned to face
+        let [x, y, _] = quaternion::rotate_vector(
+            quaternion::rotation_from_to(self.get_axis(0.0).into(), Vector3::UP.into()),
+            parent.into(),
+        );
+        if let Some(lock) = &mut self.tidal_lock {
+            lock.mean_anomaly_at_epoch = mean_anomaly_at_epoch;
+            lock.facing = y.atan2(x);
+        }
+        true
+    }
+
+    /// Makes the north pole precess around the cone centred on the current axis
+    #[must_use]
+    pub fn with_precession(mut self, precession: AxialPrecession) -> Self {
+        self.precession = Some(precession);
+        self
+    }
+
+    /// Gets the direction of the north pole at the given time
+    #[must_use]
+    pub fn get_axis(&self, time: Float) -> Vector3<Float> {
+        let Some(precession) = &self.precession else {
+            return self.axis;
+        };
+
+        let (cone_angle, angle) = precession.get_angles(time);
+
+        // Any direction perpendicular to the axis, used as the reference for the phase
+        let reference = if self.axis.x.abs() < 0.9 {
+            Vector3::RIGHT
+        } else {
+            Vector3::FORWARD
+        };
+        let perpendicular = self.axis.cross(&reference);
+        let perpendicular = perpendicular / perpendicular.magnitude();
+
+        // Tilt away from the centre of the cone, then move (clockwise) around it
+        let tilted = quaternion::rotate_vector(
+            quaternion::axis_angle(perpendicular.into(), cone_angle),
+            self.axis.into(),
+        );
+        quaternion::rotate_vector(quaternion::axis_angle(self.axis.into(), -angle), tilted).into()
+    }
+
+    /// Returns a rotation around `axis` for a given time, should just adjust the longitude the
+    /// observed body is over not the latitude.
+    #[must_use]
+    fn get_rotation(&self, time: Float, axis: Vector3<Float>) -> Quaternion<Float> {
+        quaternion::axis_angle(axis.into(), -self.get_mean_angle(time))
+    }
+
+    /// Gets angle of the prime meridian relative to the reference direction
     fn get_mean_angle(&self, time: Float) -> Float {
-        time % self.sidereal_period / self.sidereal_period * float::TAU
+        let elapsed = time - self.epoch;
+        let mut angle = elapsed % self.sidereal_period / self.sidereal_period * float::TAU
+            + self.phase
+            + self.rate_drift * elapsed * elapsed / 2.0;
+        if let Some(lock) = &self.tidal_lock {
+            // The libration follows the orbit, not the prime meridian
+            let mean_anomaly = time % self.sidereal_period / self.sidereal_period * float::TAU
+                + lock.mean_anomaly_at_epoch;
+            angle += lock.facing + lock.libration * mean_anomaly.sin();
+        }
+        angle
     }
 
     pub fn rotate_observed_bodies_equatorial_coordinates(
@@ -73,8 +409,9 @@ impl Rotating {
         time: Float,
         observations: &mut [EllipticObservation],
     ) {
-        let obliquity_rotation = quaternion::rotation_from_to(self.axis.into(), Vector3::UP.into());
-        let around_axis_rotation = self.get_rotation(time);
+        let axis = self.get_axis(time);
+        let obliquity_rotation = quaternion::rotation_from_to(axis.into(), Vector3::UP.into());
+        let around_axis_rotation = self.get_rotation(time, axis);
 
         // Convert locations to equatorial coordinates. Though the prime meridian is used instead
         // of the march equinox to mark zero longitude
@@ -96,8 +433,21 @@ mod test {
 
     use crate::{consts::float, Float};
 
+    use super::AxialPrecession;
     use super::Rotating;
     use super::SerializableRotating;
+    use crate::{
+        body::Body,
+        dynamic::{fixed::Fixed, keplerian::Keplerian, Dynamic},
+    };
+
+    /// Angle between two directions in radians
+    fn angle_between(a: Vector3<Float>, b: Vector3<Float>) -> Float {
+        use coordinates::traits::Dot;
+        (a.dot(&b) / (a.magnitude() * b.magnitude()))
+            .clamp(-1.0, 1.0)
+            .acos()
+    }
 
     #[test]
     fn normalize_axis() {
@@ -145,8 +495,10 @@ mod test {
             let (expected_y, expected_x) = (-angle).sin_cos();
 
             // Rotate the fixed point by the amount our rotating body has rotated
-            let [real_x, real_y, _] =
-                quaternion::rotate_vector(rotations.get_rotation(angle), fixed_point.into());
+            let [real_x, real_y, _] = quaternion::rotate_vector(
+                rotations.get_rotation(angle, rotations.axis),
+                fixed_point.into(),
+            );
 
             print!("Testing angle: {angle:.2}\t");
 
@@ -167,8 +519,10 @@ mod test {
 
             let (expected_z, expected_y) = (-angle).sin_cos();
 
-            let [_, real_y, real_z] =
-                quaternion::rotate_vector(rotations.get_rotation(angle), fixed_point.into());
+            let [_, real_y, real_z] = quaternion::rotate_vector(
+                rotations.get_rotation(angle, rotations.axis),
+                fixed_point.into(),
+            );
 
             print!("Testing angle: {angle:.2}\t");
 
@@ -188,8 +542,10 @@ mod test {
 
             let (expected_x, expected_z) = (-angle).sin_cos();
 
-            let [real_x, _, real_z] =
-                quaternion::rotate_vector(rotations.get_rotation(angle), fixed_point.into());
+            let [real_x, _, real_z] = quaternion::rotate_vector(
+                rotations.get_rotation(angle, rotations.axis),
+                fixed_point.into(),
+            );
 
             print!("Testing angle: {angle:.2}\t");
 
@@ -209,8 +565,10 @@ mod test {
             for i in 0..u8::MAX {
                 let angle = Float::from(i) / Float::from(u8::MAX) * float::TAU;
 
-                let [real_x, real_y, real_z] =
-                    quaternion::rotate_vector(rotations.get_rotation(angle), fixed_point.into());
+                let [real_x, real_y, real_z] = quaternion::rotate_vector(
+                    rotations.get_rotation(angle, rotations.axis),
+                    fixed_point.into(),
+                );
 
                 let (mut expected_y, mut expected_x) = (-angle).sin_cos();
                 let expected_z = fixed_point.z;
@@ -233,12 +591,283 @@ mod test {
         let rotating = Rotating {
             sidereal_period: 24.0,
             axis: Vector3::UP,
+            precession: None,
+            tidal_lock: None,
+            phase: 0.0,
+            epoch: 0.0,
+            rate_drift: 0.0,
         };
 
         let serializable: SerializableRotating = rotating.clone().into();
-        let new_rotating: Rotating = serializable.into();
+        let new_rotating: Rotating = serializable.try_into().unwrap();
 
         assert_float_absolute_eq!(rotating.sidereal_period, new_rotating.sidereal_period);
         assert_eq!(rotating.axis, new_rotating.axis);
     }
+
+    #[test]
+    fn precession_traces_cone() {
+        const CONE_ANGLE: Float = 0.4;
+        const PERIOD: Float = 1_000.0;
+        let centre = Spherical::new(1.0, 0.3, 1.0);
+        let rotating = Rotating::new(24.0, centre)
+            .with_precession(AxialPrecession::new(CONE_ANGLE, PERIOD, 0.5));
+
+        let start = rotating.get_axis(0.0);
+        for i in 0_u8..100 {
+            let axis = rotating.get_axis(Float::from(i) * 37.0);
+            assert_float_absolute_eq!(axis.magnitude(), 1.0, 1e-5);
+            assert_float_absolute_eq!(angle_between(axis, rotating.axis), CONE_ANGLE, 1e-4);
+        }
+
+        // Opposite sides of the cone after half a period, and back again after a full period
+        assert_float_absolute_eq!(
+            angle_between(start, rotating.get_axis(PERIOD / 2.0)),
+            2.0 * CONE_ANGLE,
+            1e-4
+        );
+        assert_float_absolute_eq!(angle_between(start, rotating.get_axis(PERIOD)), 0.0, 1e-3);
+
+        // No precession keeps the axis still
+        let still = Rotating::new(24.0, centre);
+        assert_eq!(still.get_axis(0.0), still.get_axis(PERIOD / 3.0));
+    }
+
+    #[test]
+    fn nutation_changes_cone_angle() {
+        let rotating = Rotating::new(24.0, Spherical::UP).with_precession(
+            AxialPrecession::new(0.4, 1_000.0, 0.0).with_nutation(0.01, 0.02, 100.0, 0.0),
+        );
+
+        assert_float_absolute_eq!(
+            angle_between(rotating.get_axis(0.0), Vector3::UP),
+            0.41,
+            1e-4
+        );
+        assert_float_absolute_eq!(
+            angle_between(rotating.get_axis(50.0), Vector3::UP),
+            0.39,
+            1e-4
+        );
+    }
+
+    #[test]
+    fn pole_star_follows_precession() {
+        let rotating = Rotating::new(24.0, Spherical::new(1.0, 0.2, 0.0))
+            .with_precession(AxialPrecession::new(0.4, 1_000.0, 0.0));
+        let body = Body::new(None, Fixed::new(Vector3::ORIGIN));
+
+        for time in [0.0, 13.0, 250.0, 600.0] {
+            // A body directly above the current north pole
+            let mut observations = [(body.clone(), rotating.get_axis(time) * 10.0)];
+            rotating.rotate_observed_bodies_equatorial_coordinates(time, &mut observations);
+
+            let location = observations[0].1;
+            assert_float_absolute_eq!(location.z, 10.0, 1e-3);
+        }
+
+        // Without precession the old pole star drifts away
+        let mut observations = [(body.clone(), rotating.get_axis(0.0) * 10.0)];
+        rotating.rotate_observed_bodies_equatorial_coordinates(500.0, &mut observations);
+        assert!(observations[0].1.z < 9.0);
+    }
+
+    #[test]
+    fn precession_serialization() {
+        // Files without precession still load
+        let json = r#"{"siderealPeriod":24.0,"axis":{"r":1.0,"theta":0.5,"phi":0.0}}"#;
+        let rotating: Rotating = serde_json::from_str(json).unwrap();
+        assert_eq!(rotating.precession, None);
+        assert!(!serde_json::to_string(&rotating)
+            .unwrap()
+            .contains("precession"));
+
+        let rotating = rotating.with_precession(
+            AxialPrecession::new(0.4, 1_000.0, 0.1).with_nutation(0.01, 0.02, 100.0, 0.3),
+        );
+        let json = serde_json::to_string(&rotating).unwrap();
+        let new: Rotating = serde_json::from_str(&json).unwrap();
+        assert_eq!(new.precession, rotating.precession);
+
+        // Phases are optional
+        let json = r#"{"siderealPeriod":24.0,"axis":{"r":1.0,"theta":0.5,"phi":0.0},"precession":{"coneAngle":0.4,"period":1000.0}}"#;
+        let rotating: Rotating = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            rotating.precession,
+            Some(AxialPrecession::new(0.4, 1_000.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn tidally_locked_parent_stays_still() {
+        let orbit = Keplerian::new(0.05, 10.0, 0.3, 0.2, 0.1, 1.0, 1.0);
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let moon = Body::new(Some(planet.clone()), orbit);
+        let rotating = Rotating::new_tidally_locked(&orbit, 0.0).unwrap();
+        assert_float_absolute_eq!(rotating.sidereal_period, orbit.get_orbital_period());
+
+        let get_planet_direction = |rotating: &Rotating, time: Float| {
+            let mut observations = [(planet.clone(), -orbit.get_offset(time))];
+            rotating.rotate_observed_bodies_equatorial_coordinates(time, &mut observations);
+            observations[0].1
+        };
+
+        // The planet only wobbles back and forth by about twice the eccentricity (the optical
+        // libration), instead of circling the sky
+        let start = get_planet_direction(&rotating, 0.0);
+        for i in 1_u8..20 {
+            let time = Float::from(i) * orbit.get_orbital_period() / 7.0;
+            let direction = get_planet_direction(&rotating, time);
+            assert!(angle_between(start, direction) < 0.25);
+        }
+
+        // Physical libration adds to the wobble, but keeps it bounded
+        let librating = Rotating::new_tidally_locked(&orbit, 0.1).unwrap();
+        let quarter = orbit.get_orbital_period() / 4.0;
+        assert!(
+            angle_between(
+                get_planet_direction(&rotating, quarter),
+                get_planet_direction(&librating, quarter)
+            ) > 0.01
+        );
+        for i in 1_u8..20 {
+            let time = Float::from(i) * orbit.get_orbital_period() / 7.0;
+            assert!(angle_between(start, get_planet_direction(&librating, time)) < 0.5);
+        }
+
+        // Unsupported orbits can't be locked to
+        assert!(Rotating::new_tidally_locked(&Fixed::new(Vector3::ORIGIN), 0.0).is_none());
+        drop(moon);
+    }
+
+    #[test]
+    fn tidally_locked_prime_meridian_faces_parent() {
+        use crate::body::observatory::Observatory;
+
+        // Whichever way the orbit is turned, and wherever the moon starts along it
+        for (i, (node, mean_anomaly)) in [(0.0, 0.0), (1.0, 2.0), (4.0, 5.5), (2.5, 3.0)]
+            .into_iter()
+            .enumerate()
+        {
+            let orbit = Keplerian::new(0.05, 10.0, 0.3, node, 0.7 * i as Float, mean_anomaly, 1.0);
+            let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+            let moon = Body::new(Some(planet.clone()), orbit);
+            moon.write().unwrap().rotation = Rotating::new_tidally_locked(&orbit, 0.0);
+            Body::hydrate_all(&planet, &None);
+
+            // Latitude 0, longitude 0
+            let observatory = Observatory::new(
+                Spherical::new(1.0, float::FRAC_PI_2, 0.0),
+                moon,
+                Err(vec![]),
+                vec![],
+            );
+            let zenith_angle = |time| {
+                observatory
+                    .observe(time)
+                    .into_iter()
+                    .find(|(body, _)| std::sync::Arc::ptr_eq(body, &planet))
+                    .map(|(_, location)| location.polar_angle)
+            };
+
+            // Overhead at the start, and stays close to it (within the optical libration)
+            assert_float_absolute_eq!(zenith_angle(0.0).unwrap(), 0.0, 1e-3);
+            for j in 1_u8..10 {
+                let time = Float::from(j) * orbit.get_orbital_period() / 7.0;
+                assert!(zenith_angle(time).unwrap() < 0.25);
+            }
+        }
+    }
+
+    #[test]
+    fn tidal_lock_serialization() {
+        // Locked rotations are derived from the orbit when the tree is hydrated
+        let json = r#"{
+            "dynamic": {"Fixed": {"x": 0.0, "y": 0.0, "z": 0.0}},
+            "children": [{
+                "dynamic": {"Circular": {"radius": 5.0, "period": 50.0, "i": 0.2, "phase": 0.0}},
+                "rotation": {"tidallyLocked": {"libration": 0.1}}
+            }]
+        }"#;
+        let root: Body = serde_json::from_str(json).unwrap();
+        let root = std::sync::Arc::new(std::sync::RwLock::new(root));
+        Body::hydrate_all(&root, &None);
+
+        let moon = root.read().unwrap().get_children()[0].clone();
+        let moon = moon.read().unwrap();
+        let rotating = moon.rotation.clone().unwrap();
+        assert!(rotating.is_tidally_locked());
+        assert_float_absolute_eq!(rotating.sidereal_period, 50.0);
+        assert_float_absolute_eq!(rotating.axis.magnitude(), 1.0, 1e-5);
+
+        let json = serde_json::to_string(&rotating).unwrap();
+        assert!(json.contains(r#""tidallyLocked":{"libration":0.1}"#));
+        let new: Rotating = serde_json::from_str(&json).unwrap();
+        assert_eq!(new.tidal_lock.map(|lock| lock.libration), Some(0.1));
+
+        // Rotations that are not locked still need a period and axis
+        assert!(serde_json::from_str::<Rotating>(r#"{"siderealPeriod":24.0}"#).is_err());
+    }
+
+    #[test]
+    fn phase_and_epoch() {
+        let rotating = Rotating::new(float::TAU, Spherical::UP).with_phase(0.5, 10.0);
+
+        // The prime meridian is at the phase at the epoch, and rotates at the same rate
+        assert_float_absolute_eq!(rotating.get_mean_angle(10.0), 0.5);
+        assert_float_absolute_eq!(rotating.get_mean_angle(11.0), 1.5, 1e-5);
+        assert_float_absolute_eq!(
+            rotating.get_mean_angle(0.0).rem_euclid(float::TAU),
+            (0.5 - 10.0 as Float).rem_euclid(float::TAU),
+            1e-5
+        );
+    }
+
+    #[test]
+    fn rate_drift() {
+        const DRIFT: Float = 1e-3;
+        let rotating = Rotating::new(float::TAU, Spherical::UP).with_rate_drift(DRIFT);
+        let steady = Rotating::new(float::TAU, Spherical::UP);
+
+        // Drift accumulates quadratically since the epoch
+        for time in [1.0, 2.0, 4.0] {
+            let difference = rotating.get_mean_angle(time) - steady.get_mean_angle(time);
+            assert_float_absolute_eq!(difference, DRIFT * time * time / 2.0, 1e-5);
+        }
+    }
+
+    #[test]
+    fn phase_serialization() {
+        // Files without a phase still load, and don't gain one
+        let json = r#"{"siderealPeriod":24.0,"axis":{"r":1.0,"theta":0.5,"phi":0.0}}"#;
+        let rotating: Rotating = serde_json::from_str(json).unwrap();
+        assert_float_absolute_eq!(rotating.get_mean_angle(0.0), 0.0);
+        let json = serde_json::to_string(&rotating).unwrap();
+        assert!(!json.contains("phase") && !json.contains("epoch") && !json.contains("rateDrift"));
+
+        let rotating = rotating.with_phase(1.0, 5.0).with_rate_drift(-1e-6);
+        let json = serde_json::to_string(&rotating).unwrap();
+        assert!(json.contains(r#""phase":1.0"#) && json.contains("rateDrift"));
+        let new: Rotating = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            (new.phase, new.epoch, new.rate_drift),
+            (rotating.phase, rotating.epoch, rotating.rate_drift)
+        );
+    }
+
+    #[test]
+    fn earth_sidereal_time() {
+        let json = include_str!("../../../assets/solar-system.json");
+        let root: Body = serde_json::from_str(json).unwrap();
+        let earth = root
+            .get_children()
+            .iter()
+            .find(|body| matches!(&body.read().unwrap().name, crate::body::Name::Named(name) if &**name == "Earth"))
+            .unwrap()
+            .clone();
+        let rotating = earth.read().unwrap().rotation.clone().unwrap();
+
+        // Greenwich mean sidereal time at J2000 is 280.46 degrees
+        assert_float_absolute_eq!(rotating.get_mean_angle(0.0).to_degrees(), 280.46, 1e-2);
+    }
 }
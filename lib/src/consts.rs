@@ -3,6 +3,9 @@ use crate::Float;
 /// Gravitational constant in terms of light seconds^3 per jupiter mass per hour^2
 pub const GRAVITATIONAL_CONSTANT: Float = 0.0609_109;
 
+/// Speed of light in light seconds per hour
+pub const SPEED_OF_LIGHT: Float = 3_600.0;
+
 /// Constants for the [`crate::Float`] type alias.
 #[cfg(any(target_arch = "wasm32", not(feature = "f64")))]
 pub mod float {
@@ -0,0 +1,65 @@
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+};
+
+use crate::Float;
+
+thread_local! {
+    /// Directory that relative CSV paths are resolved against, see [`with_base_directory`]
+    static BASE_DIRECTORY: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Errors that can occur while reading a CSV file of numbers
+#[derive(Debug)]
+pub(crate) enum ReadError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// The given line (starting at 1) could not be parsed
+    Parse(usize),
+}
+
+/// Runs `f` with relative CSV paths (e.g. of [tabulated
+/// dynamics](crate::dynamic::tabulated::Tabulated) and [horizon
+/// masks](crate::body::horizon::HorizonMask)) resolved against `directory` rather than the
+/// working directory, e.g. so a universe can be deserialized with its files next to it
+pub fn with_base_directory<T>(directory: &Path, f: impl FnOnce() -> T) -> T {
+    let previous = BASE_DIRECTORY.replace(Some(directory.to_path_buf()));
+    let result = f();
+    BASE_DIRECTORY.set(previous);
+    result
+}
+
+/// Resolves a relative `path` against the directory given to [`with_base_directory`], or leaves
+/// it relative to the working directory otherwise
+pub(crate) fn resolve(path: &Path) -> PathBuf {
+    BASE_DIRECTORY.with_borrow(|base| match base {
+        Some(base) if path.is_relative() => base.join(path),
+        _ => path.to_path_buf(),
+    })
+}
+
+/// Reads the rows of a CSV file (see [`resolve`]) that each have `columns` numbers. Blank lines,
+/// lines starting with `#`, and a single header row before the first row are ignored.
+pub(crate) fn read_rows(path: &Path, columns: usize) -> Result<Vec<Vec<Float>>, ReadError> {
+    let file = std::fs::read_to_string(resolve(path)).map_err(ReadError::Io)?;
+
+    let mut rows = Vec::new();
+    let mut has_header = false;
+    for (i, line) in file.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let values: Result<Vec<Float>, _> =
+            line.split(',').map(|v| v.trim().parse::<Float>()).collect();
+        match values {
+            Ok(values) if values.len() == columns => rows.push(values),
+            Err(_) if rows.is_empty() && !has_header => has_header = true,
+            _ => return Err(ReadError::Parse(i + 1)),
+        }
+    }
+
+    Ok(rows)
+}
@@ -0,0 +1,135 @@
+use coordinates::prelude::*;
+use dyn_partial_eq::DynPartialEq;
+use serde::{Deserialize, Serialize};
+
+use crate::Float;
+
+use super::{keplerian::Keplerian, Dynamic};
+
+/// A dynamic for bodies that orbit a shared [barycentre](https://en.wikipedia.org/wiki/Barycenter_(astronomy)),
+/// e.g. the stars in a binary star system. The parent body is expected to be a massless node that
+/// marks the centre of mass, and each child follows the relative orbit scaled by its share of the
+/// mass so that the bodies always stay on opposite sides of the barycentre.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, DynPartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Barycentric {
+    /// The orbit of the secondary body relative to the primary body
+    orbit: Keplerian,
+    /// Unit: unitless.
+    ///
+    /// Definition: The fraction of the relative orbit that this body travels, negative if this body
+    /// is on the opposite side of the barycentre to the relative orbit (i.e. the primary body).
+    scale: Float,
+}
+
+impl Barycentric {
+    /// Generates a new barycentric dynamic that follows `orbit` scaled by `scale`
+    #[must_use]
+    pub fn new(orbit: Keplerian, scale: Float) -> Self {
+        Self { orbit, scale }
+    }
+
+    /// Generates the dynamics for both bodies in a binary system from the orbit of the secondary
+    /// body relative to the primary body.
+    ///
+    /// # Returns
+    ///
+    /// `[primary, secondary]` dynamics, that should be given to children of the same barycentre
+    /// body.
+    #[must_use]
+    pub fn new_binary(orbit: Keplerian, primary_mass: Float, secondary_mass: Float) -> [Self; 2] {
+        let total_mass = primary_mass + secondary_mass;
+        [
+            Self::new(orbit, -secondary_mass / total_mass),
+            Self::new(orbit, primary_mass / total_mass),
+        ]
+    }
+
+    /// Gets the orbit of the secondary body relative to the primary body
+    #[must_use]
+    pub fn get_orbit(&self) -> &Keplerian {
+        &self.orbit
+    }
+}
+
+#[typetag::serde]
+impl Dynamic for Barycentric {
+    /// Returns the offset from the barycentre at a given time.
+    fn get_offset(&self, time: Float) -> Vector3<Float> {
+        self.orbit.get_offset(time) * self.scale
+    }
+
+    /// Returns the velocity relative to the barycentre at a given time.
+    fn get_velocity(&self, time: Float) -> Vector3<Float> {
+        self.orbit.get_velocity(time) * self.scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::body::{Arc, Body};
+
+    #[test]
+    fn opposite_sides_of_barycentre() {
+        const PRIMARY_MASS: Float = 1048.0;
+        const SECONDARY_MASS: Float = 400.0;
+        let orbit = Keplerian::new(
+            0.3,
+            5_000.0,
+            0.1,
+            0.2,
+            0.3,
+            0.4,
+            PRIMARY_MASS + SECONDARY_MASS,
+        );
+        let [primary, secondary] = Barycentric::new_binary(orbit, PRIMARY_MASS, SECONDARY_MASS);
+
+        for i in 0_u8..100 {
+            let time = Float::from(i) * 1_000.0;
+            let (a, b) = (primary.get_offset(time), secondary.get_offset(time));
+
+            // Centre of mass stays at the barycentre
+            let centre_of_mass = a * PRIMARY_MASS + b * SECONDARY_MASS;
+            assert!(centre_of_mass.magnitude() < 1e-3 * orbit.get_offset(time).magnitude());
+
+            // Separation is the relative orbit
+            assert_float_relative_eq!(
+                (b - a).magnitude(),
+                orbit.get_offset(time).magnitude(),
+                1e-4
+            );
+
+            // 180° apart
+            assert_float_absolute_eq!(a.dot(&b) / (a.magnitude() * b.magnitude()), -1.0, 1e-4);
+        }
+    }
+
+    #[test]
+    fn deserialize_in_body_tree() {
+        let orbit = r#"{"e":0.1,"a":1000.0,"i":0.0,"ascendingNode":0.0,"meanAnomaly":0.0,"epoch":0.0,"argPeri":0.0,"period":1000.0}"#;
+        let json = format!(
+            r#"{{
+                "name": "Barycentre",
+                "dynamic": {{ "Fixed": {{ "x": 0.0, "y": 0.0, "z": 0.0 }} }},
+                "children": [
+                    {{ "name": "A", "dynamic": {{ "Barycentric": {{ "orbit": {orbit}, "scale": -0.25 }} }} }},
+                    {{ "name": "B", "dynamic": {{ "Barycentric": {{ "orbit": {orbit}, "scale": 0.75 }} }} }}
+                ]
+            }}"#
+        );
+
+        let root: Arc = serde_json::from_str::<Body>(&json).unwrap().into();
+        let root = root.read().unwrap();
+        let [a, b] = [0, 1].map(|i| {
+            root.get_children()[i]
+                .read()
+                .unwrap()
+                .get_dynamic()
+                .get_offset(0.0)
+        });
+
+        assert_float_relative_eq!(a.x, -0.25 * 900.0, 1e-5);
+        assert_float_relative_eq!(b.x, 0.75 * 900.0, 1e-5);
+    }
+}
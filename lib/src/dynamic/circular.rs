@@ -0,0 +1,240 @@
+use crate::{consts::GRAVITATIONAL_CONSTANT, Float};
+use coordinates::prelude::*;
+use dyn_partial_eq::DynPartialEq;
+use quaternion::Quaternion;
+use serde::{Deserialize, Serialize};
+
+use super::{keplerian::get_orientation, Dynamic};
+
+/// Struct that models a perfectly circular orbit, equivalent to a
+/// [`crate::dynamic::keplerian::Keplerian`] with zero eccentricity but without the need to solve
+/// Kepler's equation.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, DynPartialEq)]
+#[serde(from = "IntermediateCircular", into = "IntermediateCircular")]
+pub struct Circular {
+    /// Unit: light-seconds.
+    ///
+    /// Definition: Distance from the parent body.
+    radius: Float,
+
+    /// Unit: Hours
+    ///
+    /// Definition: How long it takes for this body to complete one orbit, i.e. the
+    /// [sidereal period](https://en.wikipedia.org/wiki/Orbital_period#Related_periods).
+    orbital_period: Float,
+
+    /// Unit: radians, sort of.
+    ///
+    /// Definition: This variable encodes how the orbital plane is rotated relative to the
+    /// reference plane, encompassing the orbital inclination and the longitude of the ascending
+    /// node.
+    inclination: Quaternion<Float>,
+
+    /// Unit: radians
+    ///
+    /// Definition: The angle along the orbit at the "start of time" (t=0), measured from the same
+    /// reference direction as the argument of periapsis in
+    /// [`crate::dynamic::keplerian::Keplerian`].
+    phase: Float,
+
+    /// Unit: radians
+    ///
+    /// Definition: The orbital elements that make up [`Self::inclination`], needed for
+    /// serializing. In the order inclination, then longitude of the ascending node.
+    orientation: [Float; 2],
+}
+
+impl Circular {
+    /// Generates a new circular dynamic from the mass of the parent body
+    #[must_use]
+    pub fn new(
+        radius: Float,
+        inclination: Float,
+        longitude_of_ascending_node: Float,
+        phase: Float,
+        parent_mass: Float,
+    ) -> Self {
+        let orbital_period =
+            Float::TAU * (radius * radius * radius / (parent_mass * GRAVITATIONAL_CONSTANT)).sqrt();
+        Self::new_with_period(
+            radius,
+            inclination,
+            longitude_of_ascending_node,
+            phase,
+            orbital_period,
+        )
+    }
+
+    /// Generates a new circular dynamic, assuming you know the period of this orbit before hand.
+    #[must_use]
+    pub fn new_with_period(
+        radius: Float,
+        inclination: Float,
+        longitude_of_ascending_node: Float,
+        phase: Float,
+        orbital_period: Float,
+    ) -> Self {
+        Self {
+            radius,
+            orbital_period,
+            inclination: get_orientation(inclination, longitude_of_ascending_node, 0.0),
+            phase,
+            orientation: [inclination, longitude_of_ascending_node],
+        }
+    }
+
+    /// Gets the time taken to complete one orbit, in hours
+    #[must_use]
+    pub fn get_orbital_period(&self) -> Float {
+        self.orbital_period
+    }
+
+    /// Gets the direction of the angular momentum of the orbit, i.e. the axis that the body
+    /// travels counter-clockwise around
+    #[must_use]
+    pub fn get_orbit_normal(&self) -> Vector3<Float> {
+        // Orbits travel from the x axis towards the z axis in the reference plane
+        quaternion::rotate_vector(self.inclination, [0.0, -1.0, 0.0]).into()
+    }
+
+    /// Gets the angle along the orbit at a given time
+    pub(crate) fn get_angle(&self, time: Float) -> Float {
+        time % self.orbital_period / self.orbital_period * Float::TAU + self.phase
+    }
+}
+
+#[typetag::serde]
+impl Dynamic for Circular {
+    /// Returns the offset from the parent body at a given time.
+    fn get_offset(&self, time: Float) -> Vector3<Float> {
+        let (sin, cos) = self.get_angle(time).sin_cos();
+
+        // Top down view, rotated into the orbital plane
+        quaternion::rotate_vector(
+            self.inclination,
+            [self.radius * cos, 0.0, self.radius * sin],
+        )
+        .into()
+    }
+
+    /// Returns the velocity relative to the parent body at a given time.
+    fn get_velocity(&self, time: Float) -> Vector3<Float> {
+        let (sin, cos) = self.get_angle(time).sin_cos();
+        let speed = self.radius * Float::TAU / self.orbital_period;
+
+        quaternion::rotate_vector(self.inclination, [-speed * sin, 0.0, speed * cos]).into()
+    }
+}
+
+/// Serialized form of [`Circular`] that stores the orbital elements instead of the
+/// precalculated quaternion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IntermediateCircular {
+    /// Distance from the parent body
+    radius: Float,
+
+    /// inclination from the reference plane
+    #[serde(rename = "i", default)]
+    inclination: Float,
+    /// Location where orbit intersects the reference plane from below to above
+    #[serde(rename = "ascendingNode", default)]
+    longitude_of_ascending_node: Float,
+
+    /// Angle along the orbit at T=0
+    #[serde(default)]
+    phase: Float,
+
+    /// Time to complete one orbit, in hours
+    period: Float,
+}
+
+impl From<IntermediateCircular> for Circular {
+    fn from(value: IntermediateCircular) -> Self {
+        Circular::new_with_period(
+            value.radius,
+            value.inclination,
+            value.longitude_of_ascending_node,
+            value.phase,
+            value.period,
+        )
+    }
+}
+
+impl From<Circular> for IntermediateCircular {
+    fn from(value: Circular) -> Self {
+        let [inclination, longitude_of_ascending_node] = value.orientation;
+        IntermediateCircular {
+            radius: value.radius,
+            inclination,
+            longitude_of_ascending_node,
+            phase: value.phase,
+            period: value.orbital_period,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use coordinates::traits::Magnitude;
+
+    use super::*;
+    use crate::{
+        body::{Arc, Body},
+        dynamic::keplerian::Keplerian,
+    };
+
+    #[test]
+    fn matches_zero_eccentricity_keplerian() {
+        let circular = Circular::new_with_period(3.0, 0.4, 1.2, 2.0, 50.0);
+        let keplerian = Keplerian::new_with_period(0.0, 3.0, 0.4, 1.2, 0.0, 2.0, 50.0);
+
+        for i in 0_u8..100 {
+            let time = Float::from(i);
+            let expected = keplerian.get_offset(time);
+            let real = circular.get_offset(time);
+
+            assert_float_absolute_eq!(real.x, expected.x, 1e-4);
+            assert_float_absolute_eq!(real.y, expected.y, 1e-4);
+            assert_float_absolute_eq!(real.z, expected.z, 1e-4);
+
+            let expected = keplerian.get_velocity(time);
+            let real = circular.get_velocity(time);
+            assert_float_absolute_eq!(real.x, expected.x, 1e-4);
+            assert_float_absolute_eq!(real.y, expected.y, 1e-4);
+            assert_float_absolute_eq!(real.z, expected.z, 1e-4);
+        }
+    }
+
+    #[test]
+    fn constant_radius() {
+        let circular = Circular::new(0.5, 1.0, 2.0, 3.0, 0.01);
+
+        for i in 0_u8..100 {
+            assert_float_relative_eq!(circular.get_offset(Float::from(i)).magnitude(), 0.5, 1e-5);
+        }
+    }
+
+    #[test]
+    fn deserialize_in_body_tree() {
+        let json = r#"{
+            "name": "Station",
+            "dynamic": { "Fixed": { "x": 0.0, "y": 0.0, "z": 0.0 } },
+            "children": [{
+                "name": "Moonlet",
+                "dynamic": { "Circular": { "radius": 1.5, "period": 24.0 } }
+            }]
+        }"#;
+
+        let root: Arc = serde_json::from_str::<Body>(json).unwrap().into();
+        let root = root.read().unwrap();
+        let moonlet = root.get_children()[0].read().unwrap();
+
+        let dynamic = moonlet.get_dynamic().as_any().downcast_ref::<Circular>();
+        assert_eq!(
+            dynamic,
+            Some(&Circular::new_with_period(1.5, 0.0, 0.0, 0.0, 24.0))
+        );
+        assert_float_relative_eq!(moonlet.get_dynamic().get_offset(6.0).magnitude(), 1.5, 1e-5);
+    }
+}
@@ -1,4 +1,4 @@
-use coordinates::three_dimensional::Vector3;
+use coordinates::prelude::{ThreeDimensionalConsts, Vector3};
 use dyn_partial_eq::DynPartialEq;
 use serde::{Deserialize, Serialize};
 
@@ -24,6 +24,11 @@ impl Dynamic for Fixed {
     fn get_offset(&self, _: crate::Float) -> Vector3<crate::Float> {
         self.0
     }
+
+    /// Fixed bodies never move relative to their parent
+    fn get_velocity(&self, _: crate::Float) -> Vector3<crate::Float> {
+        Vector3::ORIGIN
+    }
 }
 
 #[cfg(test)]
@@ -41,6 +46,7 @@ mod tests {
             assert_eq!(fixed_up.get_offset(Float::from(t)), Vector3::UP);
             assert_eq!(fixed_right.get_offset(Float::from(t)), Vector3::RIGHT);
             assert_eq!(fixed_back.get_offset(Float::from(t)), Vector3::BACK);
+            assert_eq!(fixed_up.get_velocity(Float::from(t)), Vector3::ORIGIN);
         }
     }
 }
@@ -0,0 +1,451 @@
+use crate::{consts::GRAVITATIONAL_CONSTANT, Float};
+use coordinates::prelude::*;
+use dyn_partial_eq::DynPartialEq;
+use quaternion::Quaternion;
+use serde::{Deserialize, Serialize};
+
+use super::{keplerian::get_orientation, Dynamic};
+
+/// Struct that models open (unbound) orbits, i.e. [hyperbolic](https://en.wikipedia.org/wiki/Hyperbolic_trajectory)
+/// and [parabolic](https://en.wikipedia.org/wiki/Parabolic_trajectory) trajectories such as
+/// interstellar visitors, flyby comets, and escape trajectories.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, DynPartialEq)]
+#[serde(try_from = "IntermediateHyperbolic", into = "IntermediateHyperbolic")]
+pub struct Hyperbolic {
+    /// Unit: unitless.
+    ///
+    /// Definition: How open the trajectory is, `1.0` is parabolic anything larger is hyperbolic.
+    eccentricity: Float,
+    /// Unit: light-seconds.
+    ///
+    /// Definition: The closest distance between this body and the parent body.
+    periapsis_distance: Float,
+    /// Unit: light-seconds^3 per hour^2.
+    ///
+    /// Definition: The [standard gravitational parameter](https://en.wikipedia.org/wiki/Standard_gravitational_parameter)
+    /// of the parent body.
+    gravitational_parameter: Float,
+    /// Unit: hours.
+    ///
+    /// Definition: The time when this body passes through the periapsis.
+    periapsis_time: Float,
+
+    /// Unit: radians, sort of.
+    ///
+    /// Definition: This variable encodes how the trajectory is rotated relative to a reference
+    /// direction. encompassing the argument of the periapsis, the orbital inclination, and the
+    /// argument of the ascending node.
+    inclination: Quaternion<Float>,
+
+    /// Unit: radians
+    ///
+    /// Definition: The orbital elements that make up [`Self::inclination`], needed for
+    /// serializing. In the order inclination, longitude of the ascending node, and argument of
+    /// periapsis.
+    orientation: [Float; 3],
+}
+
+impl Hyperbolic {
+    /// Maximum number of iterations used when solving the hyperbolic Kepler equation.
+    const MAX_ITERATIONS: u8 = 50;
+
+    /// Generates a new hyperbolic (or parabolic if `eccentricity == 1.0`) dynamic
+    ///
+    /// # Panics
+    ///
+    /// Panics in debug builds if `eccentricity` is less than one, use a
+    /// [`crate::dynamic::keplerian::Keplerian`] for closed orbits instead.
+    #[must_use]
+    pub fn new(
+        eccentricity: Float,
+        periapsis_distance: Float,
+        inclination: Float,
+        longitude_of_ascending_node: Float,
+        argument_of_periapsis: Float,
+        periapsis_time: Float,
+        parent_mass: Float,
+    ) -> Self {
+        debug_assert!(
+            eccentricity >= 1.0,
+            "Eccentricity must be at least 1 for open trajectories"
+        );
+        Self {
+            eccentricity,
+            periapsis_distance,
+            gravitational_parameter: parent_mass * GRAVITATIONAL_CONSTANT,
+            periapsis_time,
+            inclination: get_orientation(
+                inclination,
+                longitude_of_ascending_node,
+                argument_of_periapsis,
+            ),
+            orientation: [
+                inclination,
+                longitude_of_ascending_node,
+                argument_of_periapsis,
+            ],
+        }
+    }
+
+    /// Returns true if the trajectory is exactly parabolic
+    fn is_parabolic(&self) -> bool {
+        (self.eccentricity - 1.0).abs() <= Float::EPSILON
+    }
+
+    /// Unit: light-seconds
+    ///
+    /// Gets the magnitude of the (negative) semi-major axis of a hyperbolic trajectory
+    fn get_semi_major_axis(&self) -> Float {
+        self.periapsis_distance / (self.eccentricity - 1.0)
+    }
+
+    /// Gets the hyperbolic mean anomaly, the hyperbolic equivalent of
+    /// [`crate::dynamic::keplerian::Keplerian`]'s mean anomaly. Negative before periapsis.
+    fn get_mean_anomaly(&self, time: Float) -> Float {
+        let semi_major_axis = self.get_semi_major_axis();
+        (self.gravitational_parameter / semi_major_axis.powi(3)).sqrt()
+            * (time - self.periapsis_time)
+    }
+
+    /// Solves the hyperbolic Kepler equation `M = e sinh(H) - H` for the hyperbolic anomaly `H`
+    /// using Newton's method.
+    fn get_hyperbolic_anomaly(&self, mean_anomaly: Float) -> Float {
+        let e = self.eccentricity;
+        // Starting guess that is close for both small and large mean anomalies
+        let mut result = if mean_anomaly.abs() < 6.0 * e {
+            (mean_anomaly / e).asinh()
+        } else {
+            mean_anomaly.signum() * (2.0 * mean_anomaly.abs() / e + 1.8).ln()
+        };
+
+        for _ in 0..Self::MAX_ITERATIONS {
+            let (sinh, cosh) = (result.sinh(), result.cosh());
+            let delta = (e * sinh - result - mean_anomaly) / (e * cosh - 1.0);
+            result -= delta;
+            if delta.abs() <= Float::EPSILON * result.abs().max(1.0) {
+                break;
+            }
+        }
+
+        result
+    }
+
+    /// Gets the position in the orbital plane of a parabolic trajectory using
+    /// [Barker's equation](https://en.wikipedia.org/wiki/Parabolic_trajectory#Barker's_equation)
+    fn get_parabolic_position(&self, time: Float) -> [Float; 3] {
+        let q = self.periapsis_distance;
+        let a = 1.5
+            * (self.gravitational_parameter / (2.0 * q.powi(3))).sqrt()
+            * (time - self.periapsis_time);
+        let b = (a + (a * a + 1.0).sqrt()).cbrt();
+        // tan(true anomaly / 2)
+        let d = b - 1.0 / b;
+
+        [q * (1.0 - d * d), 0.0, 2.0 * q * d]
+    }
+
+    /// Gets the velocity in the orbital plane of a parabolic trajectory
+    fn get_parabolic_velocity(&self, time: Float) -> [Float; 3] {
+        let q = self.periapsis_distance;
+        let [_, _, z] = self.get_parabolic_position(time);
+        // tan(true anomaly / 2)
+        let d = z / (2.0 * q);
+        // Rate of change of d, from differentiating Barker's equation
+        let rate = (self.gravitational_parameter / (2.0 * q.powi(3))).sqrt() / (1.0 + d * d);
+
+        [-2.0 * q * d * rate, 0.0, 2.0 * q * rate]
+    }
+
+    /// Gets the position in the orbital plane of a hyperbolic trajectory
+    fn get_hyperbolic_position(&self, time: Float) -> [Float; 3] {
+        let semi_major_axis = self.get_semi_major_axis();
+        let hyperbolic_anomaly = self.get_hyperbolic_anomaly(self.get_mean_anomaly(time));
+
+        [
+            // Equivalent to `a * (e - cosh(H))` but avoids cancellation when `e` is close to one
+            self.periapsis_distance
+                - 2.0 * semi_major_axis * (hyperbolic_anomaly / 2.0).sinh().powi(2),
+            0.0,
+            semi_major_axis
+                * (self.eccentricity * self.eccentricity - 1.0).sqrt()
+                * hyperbolic_anomaly.sinh(),
+        ]
+    }
+
+    /// Gets the velocity in the orbital plane of a hyperbolic trajectory
+    fn get_hyperbolic_velocity(&self, time: Float) -> [Float; 3] {
+        let semi_major_axis = self.get_semi_major_axis();
+        let hyperbolic_anomaly = self.get_hyperbolic_anomaly(self.get_mean_anomaly(time));
+        // Rate of change of the hyperbolic anomaly, from differentiating the hyperbolic Kepler
+        // equation
+        let rate = (self.gravitational_parameter / semi_major_axis.powi(3)).sqrt()
+            / (self.eccentricity * hyperbolic_anomaly.cosh() - 1.0);
+
+        [
+            -semi_major_axis * hyperbolic_anomaly.sinh() * rate,
+            0.0,
+            semi_major_axis
+                * (self.eccentricity * self.eccentricity - 1.0).sqrt()
+                * hyperbolic_anomaly.cosh()
+                * rate,
+        ]
+    }
+}
+
+#[typetag::serde]
+impl Dynamic for Hyperbolic {
+    /// Returns the offset from the parent body at a given time.
+    fn get_offset(&self, time: Float) -> Vector3<Float> {
+        // Top down view
+        let location = if self.is_parabolic() {
+            self.get_parabolic_position(time)
+        } else {
+            self.get_hyperbolic_position(time)
+        };
+
+        // Convert to 3d by rotating around the `longitude of the ascending node` by `inclination`
+        // radians
+        quaternion::rotate_vector(self.inclination, location).into()
+    }
+
+    /// Returns the velocity relative to the parent body at a given time.
+    fn get_velocity(&self, time: Float) -> Vector3<Float> {
+        // Top down view
+        let velocity = if self.is_parabolic() {
+            self.get_parabolic_velocity(time)
+        } else {
+            self.get_hyperbolic_velocity(time)
+        };
+
+        quaternion::rotate_vector(self.inclination, velocity).into()
+    }
+}
+
+/// Serialized form of [`Hyperbolic`] that stores the orbital elements instead of the
+/// precalculated quaternion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IntermediateHyperbolic {
+    /// eccentricity
+    e: Float,
+    /// Periapsis distance (Closest approach to the parent body)
+    #[serde(rename = "q")]
+    periapsis_distance: Float,
+
+    /// inclination from the reference plane
+    #[serde(rename = "i")]
+    inclination: Float,
+    /// Location where the trajectory intersects the reference plane from below to above
+    #[serde(rename = "ascendingNode")]
+    longitude_of_ascending_node: Float,
+    /// Location of periapsis relative to a reference point
+    #[serde(rename = "argPeri")]
+    argument_of_periapsis: Float,
+
+    /// Time of closest approach, in hours
+    periapsis_time: Float,
+    /// Mass of the parent body, in jupiter masses
+    parent_mass: Float,
+}
+
+impl TryFrom<IntermediateHyperbolic> for Hyperbolic {
+    type Error = &'static str;
+
+    fn try_from(value: IntermediateHyperbolic) -> Result<Self, Self::Error> {
+        if value.e.is_nan() || value.e < 1.0 {
+            return Err("Hyperbolic trajectories need an eccentricity of at least 1, use a Keplerian orbit for closed orbits");
+        }
+        if value.periapsis_distance.is_nan() || value.periapsis_distance <= 0.0 {
+            return Err("Hyperbolic trajectories need a positive periapsis distance");
+        }
+
+        Ok(Hyperbolic::new(
+            value.e,
+            value.periapsis_distance,
+            value.inclination,
+            value.longitude_of_ascending_node,
+            value.argument_of_periapsis,
+            value.periapsis_time,
+            value.parent_mass,
+        ))
+    }
+}
+
+impl From<Hyperbolic> for IntermediateHyperbolic {
+    fn from(value: Hyperbolic) -> Self {
+        let [inclination, longitude_of_ascending_node, argument_of_periapsis] = value.orientation;
+        IntermediateHyperbolic {
+            e: value.eccentricity,
+            periapsis_distance: value.periapsis_distance,
+            inclination,
+            longitude_of_ascending_node,
+            argument_of_periapsis,
+            periapsis_time: value.periapsis_time,
+            parent_mass: value.gravitational_parameter / GRAVITATIONAL_CONSTANT,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use coordinates::traits::Magnitude;
+
+    use super::*;
+    use crate::body::{Arc, Body};
+
+    /// Roughly the trajectory of ['Oumuamua](https://en.wikipedia.org/wiki/%CA%BBOumuamua) around
+    /// the sun
+    fn get_oumuamua() -> Hyperbolic {
+        Hyperbolic::new(
+            1.201_13,
+            0.255_912 * 499.004_8,
+            (122.74 as Float).to_radians(),
+            (24.597 as Float).to_radians(),
+            (241.81 as Float).to_radians(),
+            0.0,
+            1048.0,
+        )
+    }
+
+    #[test]
+    fn closest_approach_at_periapsis_time() {
+        let oumuamua = get_oumuamua();
+        let distance = oumuamua.get_offset(0.0).magnitude();
+        assert_float_relative_eq!(distance, oumuamua.periapsis_distance, 1e-5);
+
+        for hours in [-1_000.0, -10.0, 10.0, 1_000.0] {
+            assert!(oumuamua.get_offset(hours).magnitude() > distance);
+        }
+    }
+
+    #[test]
+    fn solves_kepler_equation() {
+        let oumuamua = get_oumuamua();
+        for mean_anomaly in [-500.0, -20.0, -1.0, -0.01, 0.0, 0.01, 1.0, 20.0, 500.0] {
+            let anomaly = oumuamua.get_hyperbolic_anomaly(mean_anomaly);
+            let residual = oumuamua.eccentricity * anomaly.sinh() - anomaly - mean_anomaly;
+            assert!(
+                residual.abs() <= 1e-4 * mean_anomaly.abs().max(1.0),
+                "Residual {residual} for mean anomaly {mean_anomaly}"
+            );
+        }
+    }
+
+    #[test]
+    fn radius_matches_conic_equation() {
+        // r = p / (1 + e cos(true anomaly)), and the true anomaly can be recovered from x and z
+        for e in [1.0, 1.01, 1.5, 3.0, 10.0] {
+            let trajectory = Hyperbolic::new(e, 10.0, 0.0, 0.0, 0.0, 0.0, 1.0);
+            let semi_latus_rectum = trajectory.periapsis_distance * (1.0 + e);
+            for time in [-50.0, -5.0, 0.0, 5.0, 50.0] {
+                let location = trajectory.get_offset(time);
+                let radius = location.magnitude();
+                let true_anomaly = location.z.atan2(location.x);
+                assert_float_relative_eq!(
+                    radius,
+                    semi_latus_rectum / (1.0 + e * true_anomaly.cos()),
+                    1e-4
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn symmetric_around_periapsis() {
+        for e in [1.0, 2.0] {
+            let trajectory = Hyperbolic::new(e, 3.0, 0.0, 0.0, 0.0, 7.0, 1.0);
+            let before = trajectory.get_offset(7.0 - 30.0);
+            let after = trajectory.get_offset(7.0 + 30.0);
+
+            assert_float_relative_eq!(before.x, after.x, 1e-4);
+            assert_float_relative_eq!(before.z, -after.z, 1e-4);
+        }
+    }
+
+    #[test]
+    fn serialization_round_trip() {
+        let oumuamua = get_oumuamua();
+        let json = serde_json::to_string(&oumuamua).unwrap();
+        let new: Hyperbolic = serde_json::from_str(&json).unwrap();
+
+        assert_float_relative_eq!(
+            new.gravitational_parameter,
+            oumuamua.gravitational_parameter,
+            1e-6
+        );
+        for (new, old) in new.orientation.iter().zip(oumuamua.orientation) {
+            assert_float_relative_eq!(*new, old, 1e-6);
+        }
+        assert_float_relative_eq!(new.eccentricity, oumuamua.eccentricity, 1e-6);
+    }
+
+    #[test]
+    fn closed_orbits_are_rejected() {
+        let json = |e: &str| {
+            format!(
+                r#"{{"e":{e},"q":10.0,"i":0.0,"ascendingNode":0.0,"argPeri":0.0,"periapsisTime":0.0,"parentMass":1.0}}"#
+            )
+        };
+        assert!(serde_json::from_str::<Hyperbolic>(&json("1.5")).is_ok());
+        assert!(serde_json::from_str::<Hyperbolic>(&json("1.0")).is_ok());
+        assert!(serde_json::from_str::<Hyperbolic>(&json("0.5")).is_err());
+
+        let json = r#"{"e":1.5,"q":-1.0,"i":0.0,"ascendingNode":0.0,"argPeri":0.0,"periapsisTime":0.0,"parentMass":1.0}"#;
+        assert!(serde_json::from_str::<Hyperbolic>(json).is_err());
+    }
+
+    #[test]
+    fn deserialize_in_body_tree() {
+        let json = r#"{
+            "name": "Sol",
+            "dynamic": { "Fixed": { "x": 0.0, "y": 0.0, "z": 0.0 } },
+            "children": [{
+                "name": "Visitor",
+                "dynamic": {
+                    "Hyperbolic": {
+                        "e": 1.2,
+                        "q": 127.7,
+                        "i": 2.14,
+                        "ascendingNode": 0.43,
+                        "argPeri": 4.22,
+                        "periapsisTime": 0.0,
+                        "parentMass": 1048.0
+                    }
+                }
+            }]
+        }"#;
+
+        let root: Arc = serde_json::from_str::<Body>(json).unwrap().into();
+        let root = root.read().unwrap();
+        let visitor = root.get_children()[0].read().unwrap();
+
+        assert!(visitor
+            .get_dynamic()
+            .as_any()
+            .downcast_ref::<Hyperbolic>()
+            .is_some());
+        assert_float_relative_eq!(
+            visitor.get_dynamic().get_offset(0.0).magnitude(),
+            127.7,
+            1e-5
+        );
+    }
+
+    #[test]
+    fn velocity_matches_finite_difference() {
+        const DELTA: Float = 0.5;
+        for e in [1.0, 1.5, 3.0] {
+            let trajectory = Hyperbolic::new(e, 10.0, 0.3, 0.2, 0.1, 0.0, 1.0);
+            for time in [-50.0, -5.0, 0.0, 5.0, 50.0] {
+                let expected = (trajectory.get_offset(time + DELTA)
+                    - trajectory.get_offset(time - DELTA))
+                    / (2.0 * DELTA);
+                let real = trajectory.get_velocity(time);
+                assert!(
+                    (real - expected).magnitude() < 1e-3 * expected.magnitude(),
+                    "e={e}, t={time}, expected {expected:?}, got {real:?}"
+                );
+            }
+        }
+    }
+}
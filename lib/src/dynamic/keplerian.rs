@@ -1,6 +1,12 @@
-use crate::{consts::GRAVITATIONAL_CONSTANT, Float};
+use std::sync::Once;
+
+use crate::{
+    consts::{float, GRAVITATIONAL_CONSTANT},
+    Float,
+};
 use coordinates::prelude::*;
 use dyn_partial_eq::DynPartialEq;
+use log::warn;
 use quaternion::Quaternion;
 use serde::{Deserialize, Serialize};
 
@@ -9,7 +15,7 @@ use super::Dynamic;
 /// Struct that best fits [kepler's laws of planetary
 /// motion](https://en.wikipedia.org/wiki/Kepler%27s_laws_of_planetary_motion).
 #[derive(Clone, Copy, Debug, Deserialize, Serialize, DynPartialEq)]
-#[serde(from = "IntermediateKeplerian", into = "IntermediateKeplerian")]
+#[serde(try_from = "IntermediateKeplerian", into = "IntermediateKeplerian")]
 pub struct Keplerian {
     // Size and shape
     /// Unit: unitless.
@@ -40,6 +46,12 @@ pub struct Keplerian {
     /// infinitely distant point and the parent body are equal again i.e. the [sidereal period](https://en.wikipedia.org/wiki/Orbital_period#Related_periods) as opposed to [tropical period](https://en.wikipedia.org/wiki/Solar_year), or [synodic period](https://en.wikipedia.org/wiki/Orbital_period#Synodic_period))
     orbital_period: Float,
 
+    /// Unit: radians per hour
+    ///
+    /// Definition: How quickly the orientation of the orbit drifts, zero for orbits with a fixed
+    /// orientation.
+    precession: Precession,
+
     /// Fields that are calculated away when Deserializing but needed for Serializeing
     calculated_fields: CalculatedFields,
 }
@@ -51,6 +63,29 @@ impl PartialEq for Keplerian {
             && self.mean_anomaly_at_epoch == other.mean_anomaly_at_epoch
             && self.orbital_period == other.orbital_period
             && self.inclination == other.inclination
+            && self.precession == other.precession
+    }
+}
+
+/// Rates that the orientation of an orbit changes over time
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Precession {
+    /// Unit: radians per hour
+    ///
+    /// The rate of [apsidal precession](https://en.wikipedia.org/wiki/Apsidal_precession), i.e.
+    /// the drift in the argument of periapsis
+    argument_of_periapsis: Float,
+    /// Unit: radians per hour
+    ///
+    /// The rate of [nodal precession](https://en.wikipedia.org/wiki/Nodal_precession), i.e. the
+    /// drift in the longitude of the ascending node
+    longitude_of_ascending_node: Float,
+}
+
+impl Precession {
+    /// Returns true if the orbit does not precess
+    fn is_fixed(&self) -> bool {
+        self.argument_of_periapsis == 0.0 && self.longitude_of_ascending_node == 0.0
     }
 }
 
@@ -73,7 +108,9 @@ struct CalculatedFields {
 }
 
 impl Keplerian {
-    /// Generates a new keplerian dynamic with the calculated fields populated
+    /// Generates a new keplerian dynamic with the calculated fields populated. Note that the
+    /// anomaly is the mean anomaly at t=0, use [`Self::true_to_mean_anomaly`] to convert from a
+    /// true anomaly.
     #[must_use]
     pub fn new(
         eccentricity: Float,
@@ -81,7 +118,7 @@ impl Keplerian {
         inclination: Float,
         longitude_of_ascending_node: Float,
         argument_of_periapsis: Float,
-        true_anomaly: Float,
+        mean_anomaly_at_epoch: Float,
         parent_mass: Float,
     ) -> Self {
         let orbital_period = Float::TAU
@@ -94,7 +131,7 @@ impl Keplerian {
             inclination,
             longitude_of_ascending_node,
             argument_of_periapsis,
-            true_anomaly,
+            mean_anomaly_at_epoch,
             orbital_period,
         )
     }
@@ -111,22 +148,17 @@ impl Keplerian {
         mean_anomaly_at_epoch: Float,
         orbital_period: Float,
     ) -> Self {
-        let inclination_quaternion =
-            quaternion::euler_angles(0.0, longitude_of_ascending_node, inclination);
-        let inclination_quaternion = quaternion::mul(
-            inclination_quaternion,
-            quaternion::axis_angle(
-                [0.0, 1.0, 0.0],
-                argument_of_periapsis + longitude_of_ascending_node,
-            ),
-        );
-
         Self {
             eccentricity,
             semi_major_axis,
-            inclination: inclination_quaternion,
+            inclination: get_orientation(
+                inclination,
+                longitude_of_ascending_node,
+                argument_of_periapsis,
+            ),
             mean_anomaly_at_epoch,
             orbital_period,
+            precession: Precession::default(),
             calculated_fields: CalculatedFields {
                 longitude_of_ascending_node,
                 argument_of_periapsis,
@@ -135,10 +167,132 @@ impl Keplerian {
         }
     }
 
+    /// Generates a new Keplerian dynamic from a [state
+    /// vector](https://en.wikipedia.org/wiki/Orbital_state_vectors), i.e. the position (in
+    /// light-seconds) and velocity (in light-seconds per hour) relative to the parent body at the
+    /// given time.
+    ///
+    /// Circular orbits have no periapsis, so the argument of periapsis is chosen so that the body
+    /// is at periapsis at the given time. Similarly the longitude of the ascending node is zero for
+    /// orbits in the reference plane.
+    ///
+    /// # Returns
+    ///
+    /// [`None`] if the body is not bound to the parent body, use a
+    /// [`crate::dynamic::hyperbolic::Hyperbolic`] instead. Also [`None`] if the body is moving
+    /// straight towards or away from the parent body, since radial trajectories have no orbital
+    /// plane.
+    #[must_use]
+    pub fn from_state_vector(
+        position: Vector3<Float>,
+        velocity: Vector3<Float>,
+        time: Float,
+        parent_mass: Float,
+    ) -> Option<Self> {
+        let gravitational_parameter = parent_mass * GRAVITATIONAL_CONSTANT;
+        let distance = position.magnitude();
+        let speed_squared = velocity.dot(&velocity);
+
+        // Specific orbital energy, zero or positive for open trajectories
+        let energy = speed_squared / 2.0 - gravitational_parameter / distance;
+        if energy.is_nan() || energy >= 0.0 {
+            return None;
+        }
+        let semi_major_axis = -gravitational_parameter / (2.0 * energy);
+
+        let angular_momentum = position.cross(&velocity);
+        if angular_momentum.magnitude() <= Float::EPSILON * distance * speed_squared.sqrt() {
+            return None;
+        }
+        let eccentricity_vector = (position * (speed_squared - gravitational_parameter / distance)
+            - velocity * position.dot(&velocity))
+            / gravitational_parameter;
+        let eccentricity = eccentricity_vector.magnitude();
+
+        // Orbits travel from the x axis towards the z axis, so the normal of an orbit in the
+        // reference plane is the negative y axis
+        let normal = angular_momentum / -angular_momentum.magnitude();
+        let inclination = normal.y.clamp(-1.0, 1.0).acos();
+        let longitude_of_ascending_node = if inclination.sin() > Float::EPSILON {
+            normal.z.atan2(-normal.x)
+        } else {
+            0.0
+        };
+
+        // Undo the inclination and the ascending node, leaving the periapsis in the reference
+        // plane
+        let periapsis = if eccentricity > Float::EPSILON.sqrt() {
+            eccentricity_vector
+        } else {
+            position
+        };
+        let periapsis = quaternion::rotate_vector(
+            quaternion::mul(
+                quaternion::axis_angle([0.0, 0.0, 1.0], -inclination),
+                quaternion::axis_angle([0.0, 1.0, 0.0], -longitude_of_ascending_node),
+            ),
+            periapsis.into(),
+        );
+        let argument_of_periapsis =
+            (-periapsis[2]).atan2(periapsis[0]) - longitude_of_ascending_node;
+
+        // Position in the reference plane, with the periapsis along the x axis
+        let [x, _, z] = quaternion::rotate_vector(
+            quaternion::conj(get_orientation(
+                inclination,
+                longitude_of_ascending_node,
+                argument_of_periapsis,
+            )),
+            position.into(),
+        );
+        let semi_minor_axis = semi_major_axis * (1.0 - eccentricity.powi(2)).sqrt();
+        let eccentric_anomaly = (z / semi_minor_axis).atan2(x / semi_major_axis + eccentricity);
+        let mean_anomaly = eccentric_anomaly - eccentricity * eccentric_anomaly.sin();
+
+        let orbital_period = Float::TAU
+            * (semi_major_axis * semi_major_axis * semi_major_axis / gravitational_parameter)
+                .sqrt();
+        let mean_anomaly_at_epoch =
+            mean_anomaly - time % orbital_period / orbital_period * Float::TAU;
+
+        Some(Self::new_with_period(
+            eccentricity,
+            semi_major_axis,
+            inclination,
+            longitude_of_ascending_node.rem_euclid(Float::TAU),
+            argument_of_periapsis.rem_euclid(Float::TAU),
+            mean_anomaly_at_epoch.rem_euclid(Float::TAU),
+            orbital_period,
+        ))
+    }
+
+    /// Gets the [state vector](https://en.wikipedia.org/wiki/Orbital_state_vectors) at a given
+    /// time, the inverse of [`Self::from_state_vector`].
+    ///
+    /// # Returns
+    ///
+    /// `(position, velocity)` relative to the parent body, in light-seconds and light-seconds per
+    /// hour.
+    #[must_use]
+    pub fn get_state_vector(&self, time: Float) -> (Vector3<Float>, Vector3<Float>) {
+        (self.get_offset(time), self.get_velocity(time))
+    }
+
+    /// Converts a [true anomaly](https://en.wikipedia.org/wiki/True_anomaly) (the angle between
+    /// the periapsis and the body) to a [mean anomaly](https://en.wikipedia.org/wiki/Mean_anomaly)
+    /// for an orbit with the given eccentricity.
+    #[must_use]
+    pub fn true_to_mean_anomaly(eccentricity: Float, true_anomaly: Float) -> Float {
+        let (sin, cos) = (true_anomaly / 2.0).sin_cos();
+        let eccentric_anomaly =
+            2.0 * ((1.0 - eccentricity).sqrt() * sin).atan2((1.0 + eccentricity).sqrt() * cos);
+        eccentric_anomaly - eccentricity * eccentric_anomaly.sin()
+    }
+
     /// Calculates the mean anomaly from the time since the epoch
     /// Note: May be larger than Tau, but should be fine since it will be used in sin or cos
     /// functions
-    fn get_mean_anomaly(&self, time: Float) -> Float {
+    pub(crate) fn get_mean_anomaly(&self, time: Float) -> Float {
         time % self.orbital_period / self.orbital_period * Float::TAU + self.mean_anomaly_at_epoch
     }
 
@@ -149,20 +303,112 @@ impl Keplerian {
             / (1.0 + self.eccentricity * mean_anomaly.cos())
     }
 
-    /// Approximates the eccentric anomaly using fixed point iteration, should be within ±0.00005 radians.
-    fn get_eccentric_anomaly(&self, mean_anomaly: Float) -> Float {
-        let mut result = mean_anomaly;
-        for _ in 0..20 {
-            result = mean_anomaly + self.eccentricity * result.sin();
+    /// Maximum number of iterations used when solving Kepler's equation, in practice the solver
+    /// converges in two to four iterations.
+    const MAX_ITERATIONS: u8 = 16;
+    /// Maximum residual of Kepler's equation for [`Self::get_eccentric_anomaly`]
+    const KEPLER_TOLERANCE: Float = 8.0 * Float::EPSILON * float::PI;
+
+    /// Solves [Kepler's equation](https://en.wikipedia.org/wiki/Kepler%27s_equation)
+    /// `M = E - e sin(E)` for the eccentric anomaly `E` using Halley's method with Danby's starting
+    /// value.
+    ///
+    /// # Accuracy
+    ///
+    /// For any eccentricity in `[0, 1)` the result satisfies Kepler's equation to within
+    /// [`Self::KEPLER_TOLERANCE`] radians, i.e. a few multiples of [`Float::EPSILON`]. The result
+    /// is in the same revolution as `mean_anomaly`.
+    #[must_use]
+    pub fn get_eccentric_anomaly(&self, mean_anomaly: Float) -> Float {
+        let e = self.eccentricity;
+        // Reduce to [-π, π] so the starting value is always close to the root
+        let revolutions = (mean_anomaly / float::TAU).round() * float::TAU;
+        let reduced = mean_anomaly - revolutions;
+
+        let mut result = reduced + 0.85 * e * reduced.signum();
+        for _ in 0..Self::MAX_ITERATIONS {
+            let (sin, cos) = result.sin_cos();
+            let f = result - e * sin - reduced;
+            if f.abs() <= Self::KEPLER_TOLERANCE {
+                break;
+            }
+            let f_prime = 1.0 - e * cos;
+            result -= f / (f_prime - 0.5 * f * e * sin / f_prime);
         }
 
-        result
+        result + revolutions
+    }
+
+    /// Adds [apsidal](https://en.wikipedia.org/wiki/Apsidal_precession) and
+    /// [nodal](https://en.wikipedia.org/wiki/Nodal_precession) precession to this orbit, i.e. the
+    /// argument of periapsis and the longitude of the ascending node drift by the given amounts
+    /// (in radians) every hour.
+    #[must_use]
+    pub fn with_precession(
+        mut self,
+        argument_of_periapsis_rate: Float,
+        longitude_of_ascending_node_rate: Float,
+    ) -> Self {
+        self.precession = Precession {
+            argument_of_periapsis: argument_of_periapsis_rate,
+            longitude_of_ascending_node: longitude_of_ascending_node_rate,
+        };
+        self
+    }
+
+    /// Gets the time taken to complete one orbit, in hours
+    #[must_use]
+    pub fn get_orbital_period(&self) -> Float {
+        self.orbital_period
+    }
+
+    /// Gets the direction of the angular momentum of the orbit at the "start of time" (t=0), i.e.
+    /// the axis that the body travels counter-clockwise around
+    #[must_use]
+    pub fn get_orbit_normal(&self) -> Vector3<Float> {
+        // Orbits travel from the x axis towards the z axis in the reference plane
+        quaternion::rotate_vector(self.inclination, [0.0, -1.0, 0.0]).into()
     }
 
+    /// Gets the orientation of the orbit at the "start of time" (t=0)
     #[must_use]
     pub fn get_inclination(&self) -> &Quaternion<Float> {
         &self.inclination
     }
+
+    /// Gets the orientation of the orbit at a given time, taking precession into account
+    #[must_use]
+    pub fn get_inclination_at(&self, time: Float) -> Quaternion<Float> {
+        if self.precession.is_fixed() {
+            self.inclination
+        } else {
+            get_orientation(
+                self.calculated_fields.inclination,
+                self.calculated_fields.longitude_of_ascending_node
+                    + self.precession.longitude_of_ascending_node * time,
+                self.calculated_fields.argument_of_periapsis
+                    + self.precession.argument_of_periapsis * time,
+            )
+        }
+    }
+}
+
+/// Generates the quaternion that rotates an orbit from the reference plane (with the periapsis
+/// along the positive x axis) to its true orientation.
+pub(crate) fn get_orientation(
+    inclination: Float,
+    longitude_of_ascending_node: Float,
+    argument_of_periapsis: Float,
+) -> Quaternion<Float> {
+    let inclination_quaternion =
+        quaternion::euler_angles(0.0, longitude_of_ascending_node, inclination);
+    quaternion::mul(
+        inclination_quaternion,
+        quaternion::axis_angle(
+            [0.0, 1.0, 0.0],
+            argument_of_periapsis + longitude_of_ascending_node,
+        ),
+    )
 }
 
 #[typetag::serde]
@@ -178,10 +424,52 @@ impl Dynamic for Keplerian {
         // Convert to 3d by rotating around the `longitude of the ascending node` by `inclination`
         // radians
         let location = [x, 0.0, z];
-        quaternion::rotate_vector(self.inclination, location).into()
+        quaternion::rotate_vector(self.get_inclination_at(time), location).into()
+    }
+
+    /// Returns the velocity relative to the parent body at a given time.
+    fn get_velocity(&self, time: Float) -> Vector3<Float> {
+        let eccentric_anomaly = self.get_eccentric_anomaly(self.get_mean_anomaly(time));
+        let (sin, cos) = eccentric_anomaly.sin_cos();
+        let semi_minor_axis = self.semi_major_axis * (1.0 - self.eccentricity.powi(2)).sqrt();
+        // Rate of change of the eccentric anomaly, from differentiating Kepler's equation
+        let rate = Float::TAU / self.orbital_period / (1.0 - self.eccentricity * cos);
+
+        // Top down view
+        let velocity = [
+            -self.semi_major_axis * sin * rate,
+            0.0,
+            semi_minor_axis * cos * rate,
+        ];
+        let velocity: Vector3<Float> =
+            quaternion::rotate_vector(self.get_inclination_at(time), velocity).into();
+
+        if self.precession.is_fixed() {
+            velocity
+        } else {
+            // Add the motion of the orbit itself, precession is slow enough that a central
+            // difference of the orientation is accurate
+            let delta = super::get_difference_step(1.0, time);
+            let location = [
+                self.semi_major_axis * (cos - self.eccentricity),
+                0.0,
+                semi_minor_axis * sin,
+            ];
+            let after: Vector3<Float> =
+                quaternion::rotate_vector(self.get_inclination_at(time + delta), location).into();
+            let before: Vector3<Float> =
+                quaternion::rotate_vector(self.get_inclination_at(time - delta), location).into();
+            velocity + (after - before) / (2.0 * delta)
+        }
     }
 }
 
+/// Serialized form of [`Keplerian`] that stores the orbital elements instead of the
+/// precalculated quaternion.
+///
+/// The position along the orbit is given by either `meanAnomaly` or `trueAnomaly` at the time
+/// `epoch`. Files written before `epoch` existed only had a `trueAnomaly`, so a `trueAnomaly`
+/// without an `epoch` is still read as the true anomaly at t=0 with a deprecation warning.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct IntermediateKeplerian {
@@ -198,27 +486,83 @@ struct IntermediateKeplerian {
     #[serde(rename = "ascendingNode")]
     longitude_of_ascending_node: Float,
 
-    /// Anomaly at T=0
-    true_anomaly: Float,
+    /// Mean anomaly at [`Self::epoch`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mean_anomaly: Option<Float>,
+    /// True anomaly at [`Self::epoch`], or at T=0 in legacy files without an epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    true_anomaly: Option<Float>,
+    /// Time that the anomaly is given for, in hours
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    epoch: Option<Float>,
     /// Location of periapsis relative to a reference point
     #[serde(rename = "argPeri")]
     argument_of_periapsis: Float,
 
     /// Time to complete one orbit, in hours
     period: Float,
+
+    /// Drift in the argument of periapsis, in radians per hour
+    #[serde(rename = "argPeriPrecession", default, skip_serializing_if = "is_zero")]
+    argument_of_periapsis_precession: Float,
+    /// Drift in the longitude of the ascending node, in radians per hour
+    #[serde(
+        rename = "ascendingNodePrecession",
+        default,
+        skip_serializing_if = "is_zero"
+    )]
+    longitude_of_ascending_node_precession: Float,
 }
 
-impl From<IntermediateKeplerian> for Keplerian {
-    fn from(value: IntermediateKeplerian) -> Self {
-        Keplerian::new_with_period(
+/// Returns true if a value is zero, used to skip serializing optional rates
+fn is_zero(value: &Float) -> bool {
+    *value == 0.0
+}
+
+/// Used to only warn about legacy anomalies once, since generated universes can have many orbits
+static LEGACY_ANOMALY_WARNING: Once = Once::new();
+
+impl TryFrom<IntermediateKeplerian> for Keplerian {
+    type Error = &'static str;
+
+    fn try_from(value: IntermediateKeplerian) -> Result<Self, Self::Error> {
+        let mean_anomaly = match (value.mean_anomaly, value.true_anomaly, value.epoch) {
+            (Some(_), Some(_), _) => {
+                return Err("Keplerian orbits need either a meanAnomaly or a trueAnomaly, not both")
+            }
+            (None, None, _) => return Err("Keplerian orbits need a meanAnomaly or a trueAnomaly"),
+            (Some(mean_anomaly), None, _) => mean_anomaly,
+            (None, Some(true_anomaly), epoch) => {
+                if epoch.is_none() {
+                    LEGACY_ANOMALY_WARNING.call_once(|| {
+                        warn!(
+                            "Keplerian orbits with a trueAnomaly but no epoch are deprecated, the \
+                            value is used as the true anomaly at t=0. Add an epoch, or use \
+                            meanAnomaly instead"
+                        );
+                    });
+                }
+                Keplerian::true_to_mean_anomaly(value.e, true_anomaly)
+            }
+        };
+
+        // Wind the anomaly back from the epoch to t=0
+        let epoch = value.epoch.unwrap_or(0.0);
+        let mean_anomaly_at_epoch = mean_anomaly - epoch % value.period / value.period * Float::TAU;
+
+        Ok(Keplerian::new_with_period(
             value.e,
             value.semimajor_axis,
             value.inclination,
             value.longitude_of_ascending_node,
             value.argument_of_periapsis,
-            value.true_anomaly,
+            mean_anomaly_at_epoch,
             value.period,
         )
+        .with_precession(
+            value.argument_of_periapsis_precession,
+            value.longitude_of_ascending_node_precession,
+        ))
     }
 }
 impl From<Keplerian> for IntermediateKeplerian {
@@ -229,8 +573,12 @@ impl From<Keplerian> for IntermediateKeplerian {
             inclination: value.calculated_fields.inclination,
             argument_of_periapsis: value.calculated_fields.argument_of_periapsis,
             longitude_of_ascending_node: value.calculated_fields.longitude_of_ascending_node,
-            true_anomaly: value.mean_anomaly_at_epoch,
+            mean_anomaly: Some(value.mean_anomaly_at_epoch),
+            true_anomaly: None,
+            epoch: Some(0.0),
             period: value.orbital_period,
+            argument_of_periapsis_precession: value.precession.argument_of_periapsis,
+            longitude_of_ascending_node_precession: value.precession.longitude_of_ascending_node,
         }
     }
 }
@@ -348,6 +696,90 @@ mod tests {
         }
     }
 
+    #[test]
+    fn kepler_equation_accuracy() {
+        for e in (0..=999_u16).step_by(37).chain([998, 999]) {
+            let orbit = Keplerian::new_with_period(
+                Float::from(e) / 1000.0,
+                1.0,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+                Float::TAU,
+            );
+            for i in -200_i16..=200 {
+                let mean_anomaly = Float::from(i) / 50.0;
+                let eccentric_anomaly = orbit.get_eccentric_anomaly(mean_anomaly);
+                let residual =
+                    eccentric_anomaly - orbit.eccentricity * eccentric_anomaly.sin() - mean_anomaly;
+                assert!(
+                    residual.abs() <= Keplerian::KEPLER_TOLERANCE,
+                    "e = {}, M = {mean_anomaly}, residual = {residual}",
+                    orbit.eccentricity
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn high_eccentricity_periapsis_and_apoapsis() {
+        let comet = Keplerian::new_with_period(0.995, 100.0, 0.0, 0.0, 0.0, 0.0, 1000.0);
+
+        let periapsis = comet.get_offset(0.0);
+        assert_float_relative_eq!(periapsis.x, 100.0 * (1.0 - 0.995), 1e-4);
+
+        let apoapsis = comet.get_offset(500.0);
+        assert_float_relative_eq!(apoapsis.x, -100.0 * (1.0 + 0.995), 1e-4);
+    }
+
+    #[test]
+    fn precession_rotates_orbit() {
+        const QUARTER_TURN_TIME: Float = 1_000.0;
+        const RATE: Float = float::FRAC_PI_2 / QUARTER_TURN_TIME;
+        let start = Keplerian::new_with_period(0.3, 10.0, 0.5, 0.2, 0.1, 0.0, 100.0);
+
+        let apsidal = start.with_precession(RATE, 0.0);
+        let expected =
+            Keplerian::new_with_period(0.3, 10.0, 0.5, 0.2, 0.1 + float::FRAC_PI_2, 0.0, 100.0);
+        let (real, expected) = (
+            apsidal.get_offset(QUARTER_TURN_TIME),
+            expected.get_offset(QUARTER_TURN_TIME),
+        );
+        assert_float_absolute_eq!(real.x, expected.x, 1e-4);
+        assert_float_absolute_eq!(real.y, expected.y, 1e-4);
+        assert_float_absolute_eq!(real.z, expected.z, 1e-4);
+
+        let nodal = start.with_precession(0.0, RATE);
+        let expected =
+            Keplerian::new_with_period(0.3, 10.0, 0.5, 0.2 + float::FRAC_PI_2, 0.1, 0.0, 100.0);
+        let (real, expected) = (
+            nodal.get_offset(QUARTER_TURN_TIME),
+            expected.get_offset(QUARTER_TURN_TIME),
+        );
+        assert_float_absolute_eq!(real.x, expected.x, 1e-4);
+        assert_float_absolute_eq!(real.y, expected.y, 1e-4);
+        assert_float_absolute_eq!(real.z, expected.z, 1e-4);
+
+        // No drift at the epoch
+        assert_eq!(nodal.get_offset(0.0), start.get_offset(0.0));
+    }
+
+    #[test]
+    fn precession_serialization() {
+        let json = r#"{"e":0.1,"a":1.0,"i":0.2,"ascendingNode":0.3,"meanAnomaly":0.4,"argPeri":0.5,"period":10.0}"#;
+        let fixed: Keplerian = serde_json::from_str(json).unwrap();
+        assert!(fixed.precession.is_fixed());
+        assert!(!serde_json::to_string(&fixed)
+            .unwrap()
+            .contains("Precession"));
+
+        let precessing = fixed.with_precession(1e-6, -2e-6);
+        let json = serde_json::to_string(&precessing).unwrap();
+        let new: Keplerian = serde_json::from_str(&json).unwrap();
+        assert_eq!(new.precession, precessing.precession);
+    }
+
     #[test]
     /// The mean anomaly and the eccentric anomaly should always be equal when there is zero
     /// eccentricity
@@ -363,4 +795,167 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn velocity_matches_vis_viva() {
+        const PARENT_MASS: Float = 1_000.0;
+        let orbit = Keplerian::new(0.6, 100.0, 0.3, 1.2, 2.1, 0.5, PARENT_MASS);
+
+        for i in 0_u8..100 {
+            let time = Float::from(i) * 7.0;
+            let distance = orbit.get_offset(time).magnitude();
+            let expected =
+                (PARENT_MASS * GRAVITATIONAL_CONSTANT * (2.0 / distance - 1.0 / 100.0)).sqrt();
+
+            assert_float_relative_eq!(orbit.get_velocity(time).magnitude(), expected, 1e-4);
+        }
+    }
+
+    #[test]
+    fn velocity_matches_finite_difference() {
+        const DELTA: Float = 0.5;
+        let orbits = [
+            Keplerian::new_with_period(0.3, 100.0, 0.3, 1.2, 2.1, 0.5, 1_000.0),
+            Keplerian::new_with_period(0.3, 100.0, 0.3, 1.2, 2.1, 0.5, 1_000.0)
+                .with_precession(1e-3, -2e-3),
+        ];
+
+        for orbit in orbits {
+            for i in 0_u8..100 {
+                let time = Float::from(i) * 13.0;
+                let expected = (orbit.get_offset(time + DELTA) - orbit.get_offset(time - DELTA))
+                    / (2.0 * DELTA);
+                let real = orbit.get_velocity(time);
+
+                assert!(
+                    (real - expected).magnitude() < 1e-3 * expected.magnitude(),
+                    "t={time}, expected {expected:?}, got {real:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn state_vector_round_trip() {
+        const PARENT_MASS: Float = 1_000.0;
+        for (e, i, lan, aop) in [
+            (0.3, 0.4, 1.2, 2.1),
+            (0.7, 2.5, 4.0, 0.3),
+            (0.1, 1.5, 0.2, 5.5),
+            (0.0, 0.3, 0.5, 0.0),
+            (0.2, 0.0, 0.0, 1.0),
+        ] {
+            let orbit = Keplerian::new(e, 100.0, i, lan, aop, 0.7, PARENT_MASS);
+            for time in [0.0, 123.0, 4_567.0] {
+                let (position, velocity) = orbit.get_state_vector(time);
+                let converted =
+                    Keplerian::from_state_vector(position, velocity, time, PARENT_MASS).unwrap();
+
+                assert_float_absolute_eq!(converted.eccentricity, e, 1e-4);
+                assert_float_relative_eq!(converted.semi_major_axis, 100.0, 1e-4);
+                assert_float_absolute_eq!(converted.calculated_fields.inclination, i, 1e-4);
+                assert_float_relative_eq!(converted.orbital_period, orbit.orbital_period, 1e-4);
+
+                // The orbits are equal even when the angles are not unique
+                for offset in [-50.0, 0.0, 30.0, 700.0] {
+                    let expected = orbit.get_offset(time + offset);
+                    let real = converted.get_offset(time + offset);
+                    assert!(
+                        (real - expected).magnitude() < 1e-3 * expected.magnitude(),
+                        "e={e}, i={i}, t={time}: expected {expected:?}, got {real:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn state_vector_recovers_elements() {
+        let orbit = Keplerian::new(0.3, 100.0, 0.4, 1.2, 2.1, 0.7, 1_000.0);
+        let (position, velocity) = orbit.get_state_vector(0.0);
+        let converted = Keplerian::from_state_vector(position, velocity, 0.0, 1_000.0).unwrap();
+
+        assert_float_absolute_eq!(
+            converted.calculated_fields.longitude_of_ascending_node,
+            1.2,
+            1e-4
+        );
+        assert_float_absolute_eq!(converted.calculated_fields.argument_of_periapsis, 2.1, 1e-4);
+        assert_float_absolute_eq!(converted.mean_anomaly_at_epoch, 0.7, 1e-4);
+    }
+
+    #[test]
+    fn unbound_state_vector() {
+        let escape_speed = (2.0 * GRAVITATIONAL_CONSTANT * 1_000.0 / 100.0).sqrt();
+        assert!(Keplerian::from_state_vector(
+            Vector3::RIGHT * 100.0,
+            Vector3::UP * (escape_speed * 1.01),
+            0.0,
+            1_000.0
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn radial_state_vector() {
+        // Falling straight towards the parent, and momentarily at rest
+        for velocity in [Vector3::RIGHT * -0.01, Vector3::ORIGIN] {
+            assert!(
+                Keplerian::from_state_vector(Vector3::RIGHT * 100.0, velocity, 0.0, 1_000.0)
+                    .is_none()
+            );
+        }
+    }
+
+    #[test]
+    fn true_anomaly_conversion() {
+        // Periapsis and apoapsis are the same for both anomalies
+        assert_float_absolute_eq!(Keplerian::true_to_mean_anomaly(0.5, 0.0), 0.0);
+        assert_float_absolute_eq!(
+            Keplerian::true_to_mean_anomaly(0.5, float::PI).abs(),
+            float::PI,
+            1e-5
+        );
+        // And equal for circular orbits
+        assert_float_absolute_eq!(Keplerian::true_to_mean_anomaly(0.0, 1.0), 1.0, 1e-6);
+
+        // The body is at the given true anomaly at the epoch
+        let json = r#"{"e":0.6,"a":10.0,"i":0.0,"ascendingNode":0.0,"argPeri":0.0,"trueAnomaly":1.0,"epoch":25.0,"period":100.0}"#;
+        let orbit: Keplerian = serde_json::from_str(json).unwrap();
+        let location = orbit.get_offset(25.0);
+        assert_float_absolute_eq!(location.z.atan2(location.x), 1.0, 1e-4);
+    }
+
+    #[test]
+    fn anomaly_formats() {
+        let base = r#""e":0.6,"a":10.0,"i":0.0,"ascendingNode":0.0,"argPeri":0.0,"period":100.0"#;
+        let parse =
+            |anomaly: &str| serde_json::from_str::<Keplerian>(&format!("{{{base},{anomaly}}}"));
+
+        let mean = parse(r#""meanAnomaly":1.0"#).unwrap();
+        assert_eq!(parse(r#""meanAnomaly":1.0,"epoch":0.0"#).unwrap(), mean);
+
+        // Legacy files use `trueAnomaly` at t=0, which is converted rather than used as the mean
+        // anomaly
+        let legacy = parse(r#""trueAnomaly":1.0"#).unwrap();
+        assert_eq!(legacy, parse(r#""trueAnomaly":1.0,"epoch":0.0"#).unwrap());
+        assert_float_absolute_eq!(
+            legacy.mean_anomaly_at_epoch,
+            Keplerian::true_to_mean_anomaly(0.6, 1.0),
+            1e-6
+        );
+        assert!((legacy.mean_anomaly_at_epoch - 1.0).abs() > 0.1);
+
+        // Winding back from a later epoch
+        let later = parse(r#""meanAnomaly":1.0,"epoch":25.0"#).unwrap();
+        assert_float_absolute_eq!(later.mean_anomaly_at_epoch, 1.0 - float::FRAC_PI_2, 1e-6);
+
+        assert!(parse(r#""meanAnomaly":1.0,"trueAnomaly":1.0"#).is_err());
+        assert!(parse(r#""epoch":1.0"#).is_err());
+
+        // New files are written with an explicit mean anomaly and epoch
+        let json = serde_json::to_string(&mean).unwrap();
+        assert!(json.contains("meanAnomaly") && json.contains("epoch"));
+        assert!(!json.contains("trueAnomaly"));
+    }
 }
@@ -1,8 +1,17 @@
+/// A dynamic for bodies that orbit a shared centre of mass, e.g. binary stars.
+pub mod barycentric;
+/// A dynamic that follows a perfectly circular orbit.
+pub mod circular;
 /// A dynamic that stays in a constant location
 pub mod fixed;
+/// A dynamic that follows an open [hyperbolic](https://en.wikipedia.org/wiki/Hyperbolic_trajectory) or parabolic trajectory.
+pub mod hyperbolic;
 /// A dynamic that fits the [keplerian laws for planetary motion](https://en.wikipedia.org/wiki/Kepler%27s_laws_of_planetary_motion).
 pub mod keplerian;
-//mod circular;
+/// Numerical integration of bodies that attract each other, with results exposed as dynamics.
+pub mod n_body;
+/// A dynamic that interpolates between precomputed offsets.
+pub mod tabulated;
 
 use std::fmt::Debug;
 
@@ -19,6 +28,56 @@ pub trait Dynamic: Debug + Send + Sync + DynClone {
     /// Returns the position relative to the parent body at a given time.
     #[must_use]
     fn get_offset(&self, time: Float) -> Vector3<Float>;
+
+    /// Returns the velocity relative to the parent body at a given time, in light-seconds per
+    /// hour.
+    ///
+    /// The default implementation approximates the velocity with a central difference of
+    /// [`Self::get_offset`], dynamics should override it with an analytic solution where one
+    /// exists.
+    #[must_use]
+    fn get_velocity(&self, time: Float) -> Vector3<Float> {
+        let delta = get_difference_step(1e-3, time);
+        (self.get_offset(time + delta) - self.get_offset(time - delta)) / (2.0 * delta)
+    }
 }
 
 dyn_clone::clone_trait_object!(Dynamic);
+
+/// Unit: hours
+///
+/// Definition: Step for a central difference around `time`, at least `minimum` but large enough
+/// that `time ± step` is still resolved far from the epoch, especially with 32 bit floats
+pub(crate) fn get_difference_step(minimum: Float, time: Float) -> Float {
+    minimum.max(time.abs() * Float::EPSILON.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use coordinates::three_dimensional::Vector3;
+    use dyn_partial_eq::DynPartialEq;
+    use serde::{Deserialize, Serialize};
+
+    use super::Dynamic;
+    use crate::Float;
+
+    /// Moves in a straight line, using the default velocity
+    #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, DynPartialEq)]
+    struct Linear;
+
+    #[typetag::serde]
+    impl Dynamic for Linear {
+        fn get_offset(&self, time: Float) -> Vector3<Float> {
+            Vector3::from((time, 2.0 * time, 0.0))
+        }
+    }
+
+    #[test]
+    fn default_velocity_far_from_epoch() {
+        for time in [0.0, 1.0, 1e4, 1e5, 1e7, -1e7] {
+            let velocity = Linear.get_velocity(time);
+            assert_float_relative_eq!(velocity.x, 1.0, 1e-2);
+            assert_float_relative_eq!(velocity.y, 2.0, 1e-2);
+        }
+    }
+}
@@ -0,0 +1,447 @@
+use coordinates::prelude::*;
+use dyn_partial_eq::DynPartialEq;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    body::{Arc, Body},
+    consts::GRAVITATIONAL_CONSTANT,
+    Float,
+};
+
+use super::{tabulated::Tabulated, Dynamic};
+
+/// Settings for replacing the analytic dynamics of a body tree with a numerical
+/// [N-body simulation](https://en.wikipedia.org/wiki/N-body_simulation), useful for close
+/// multi-planet systems where bodies perturb each other's orbits.
+///
+/// Initial positions and velocities are taken from the existing dynamics at the start time, and
+/// bodies attract each other according to their [mass](crate::body::Body::get_mass). Bodies
+/// without a mass are treated as test particles, they are attracted by other bodies but do not
+/// attract anything themselves. The results are given to each descendant of the root in a copy
+/// of the tree as an [`Integrated`] dynamic relative to its parent, so observatories and outputs
+/// work unchanged while the original tree is left as it was.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NBody {
+    /// Unit: hours.
+    ///
+    /// Definition: Time between integration steps, also the spacing of the resulting
+    /// [`Tabulated`] entries.
+    step: Float,
+    /// The method used to advance the simulation each step
+    #[serde(default)]
+    integrator: Integrator,
+}
+
+/// Numerical methods for advancing an [`NBody`] simulation
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum Integrator {
+    /// [Kick-drift-kick leapfrog](https://en.wikipedia.org/wiki/Leapfrog_integration), a second
+    /// order symplectic method that conserves energy well over long time spans.
+    #[default]
+    Leapfrog,
+    /// The classic fourth order [Runge-Kutta
+    /// method](https://en.wikipedia.org/wiki/Runge%E2%80%93Kutta_methods), more accurate per step
+    /// but slowly drifts in energy.
+    RungeKutta4,
+}
+
+/// The motion of a body from an [`NBody`] integration, which falls back to the original dynamic
+/// of the body outside of the integrated times
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, DynPartialEq)]
+pub struct Integrated {
+    /// Offsets from the parent body over the integrated times
+    table: Tabulated,
+    /// The dynamic the body had before integration
+    original: Box<dyn Dynamic>,
+}
+
+#[typetag::serde]
+impl Dynamic for Integrated {
+    /// Returns the integrated offset from the parent body, or the offset from the original
+    /// dynamic outside of the integrated times
+    fn get_offset(&self, time: Float) -> Vector3<Float> {
+        if self.table.covers(time) {
+            self.table.get_offset(time)
+        } else {
+            self.original.get_offset(time)
+        }
+    }
+
+    /// Returns the integrated velocity relative to the parent body, or the velocity from the
+    /// original dynamic outside of the integrated times
+    fn get_velocity(&self, time: Float) -> Vector3<Float> {
+        if self.table.covers(time) {
+            self.table.get_velocity(time)
+        } else {
+            self.original.get_velocity(time)
+        }
+    }
+}
+
+/// A body taking part in the simulation
+struct Particle {
+    /// The body that will be given the integrated dynamic
+    body: Arc,
+    /// Index of the parent particle, [`None`] for the root of the simulation
+    parent: Option<usize>,
+    /// Unit: jupiter masses
+    mass: Float,
+}
+
+impl NBody {
+    /// Generates new N-body settings with integration steps every `step` hours.
+    #[must_use]
+    pub fn new(step: Float, integrator: Integrator) -> Self {
+        Self { step, integrator }
+    }
+
+    /// Integrates the motion of every body in the tree under `root` from `start_time` to
+    /// `end_time` (in hours), and returns a copy of the tree (see [`Body::deep_clone`]) where the
+    /// descendants of the root follow the results. The root is treated as the origin of an
+    /// inertial frame and its dynamic is left unchanged, as is the original tree.
+    // Precision loss is acceptable since the number of steps is far smaller than the mantissa
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    #[must_use]
+    pub fn integrate(&self, root: &Arc, start_time: Float, end_time: Float) -> Arc {
+        let root = Body::deep_clone(root);
+        if self.step.is_nan() || self.step <= 0.0 {
+            warn!(
+                "N-body step must be positive, got {}, skipping integration",
+                self.step
+            );
+            return root;
+        }
+
+        let mut particles = Vec::new();
+        Self::collect_particles(&root, None, &mut particles);
+        let masses: Vec<Float> = particles.iter().map(|p| p.mass).collect();
+
+        let mut positions = Self::get_absolute(&particles, start_time, |dynamic, time| {
+            dynamic.get_offset(time)
+        });
+        let mut velocities = Self::get_absolute(&particles, start_time, |dynamic, time| {
+            dynamic.get_velocity(time)
+        });
+
+        let steps = ((end_time - start_time) / self.step).ceil().max(1.0) as usize;
+        info!("Integrating {} bodies over {steps} steps", particles.len());
+
+        let mut tables: Vec<Vec<(Float, Vector3<Float>)>> =
+            vec![Vec::with_capacity(steps + 1); particles.len()];
+        Self::record(&particles, &positions, start_time, &mut tables);
+
+        let mut accelerations = Self::get_accelerations(&positions, &masses);
+        for i in 1..=steps {
+            match self.integrator {
+                Integrator::Leapfrog => {
+                    let half_step = self.step * 0.5;
+                    for ((position, velocity), acceleration) in positions
+                        .iter_mut()
+                        .zip(velocities.iter_mut())
+                        .zip(&accelerations)
+                    {
+                        *velocity = *velocity + *acceleration * half_step;
+                        *position = *position + *velocity * self.step;
+                    }
+                    accelerations = Self::get_accelerations(&positions, &masses);
+                    for (velocity, acceleration) in velocities.iter_mut().zip(&accelerations) {
+                        *velocity = *velocity + *acceleration * half_step;
+                    }
+                }
+                Integrator::RungeKutta4 => {
+                    (positions, velocities) =
+                        self.runge_kutta_step(&positions, &velocities, &masses);
+                }
+            }
+
+            Self::record(
+                &particles,
+                &positions,
+                start_time + i as Float * self.step,
+                &mut tables,
+            );
+        }
+
+        for (particle, table) in particles.iter().zip(tables) {
+            if particle.parent.is_none() {
+                continue;
+            }
+            match (Tabulated::new(table), particle.body.write()) {
+                (Ok(table), Ok(mut body)) => {
+                    let original = body.dynamic.clone();
+                    body.dynamic = Box::new(Integrated { table, original });
+                }
+                (Err(e), _) => warn!("Could not tabulate N-body results: {e}"),
+                (_, Err(e)) => warn!("Could not update body with N-body results: {e}"),
+            }
+        }
+
+        root
+    }
+
+    /// Adds `body` and all of its descendants to `particles`, parents always come before their
+    /// children
+    fn collect_particles(body: &Arc, parent: Option<usize>, particles: &mut Vec<Particle>) {
+        let Ok(lock) = body.read() else {
+            return;
+        };
+
+        let index = particles.len();
+        particles.push(Particle {
+            body: body.clone(),
+            parent,
+            mass: lock.get_mass().unwrap_or(0.0),
+        });
+
+        for child in lock.get_children() {
+            Self::collect_particles(child, Some(index), particles);
+        }
+    }
+
+    /// Gets the location (or velocity) of each particle relative to the root by summing `value`
+    /// from the current dynamics
+    fn get_absolute(
+        particles: &[Particle],
+        time: Float,
+        value: fn(&dyn Dynamic, Float) -> Vector3<Float>,
+    ) -> Vec<Vector3<Float>> {
+        let mut results: Vec<Vector3<Float>> = Vec::with_capacity(particles.len());
+        for particle in particles {
+            let result = match particle.parent {
+                Some(parent) => {
+                    results[parent]
+                        + particle
+                            .body
+                            .read()
+                            .map_or(Vector3::ORIGIN, |b| value(b.get_dynamic(), time))
+                }
+                None => Vector3::ORIGIN,
+            };
+            results.push(result);
+        }
+        results
+    }
+
+    /// Adds the offset of each particle from its parent to the tables
+    fn record(
+        particles: &[Particle],
+        positions: &[Vector3<Float>],
+        time: Float,
+        tables: &mut [Vec<(Float, Vector3<Float>)>],
+    ) {
+        for ((particle, position), table) in particles.iter().zip(positions).zip(tables) {
+            if let Some(parent) = particle.parent {
+                table.push((time, *position - positions[parent]));
+            }
+        }
+    }
+
+    /// Gets the gravitational acceleration of each particle due to every massive particle
+    fn get_accelerations(positions: &[Vector3<Float>], masses: &[Float]) -> Vec<Vector3<Float>> {
+        positions
+            .iter()
+            .enumerate()
+            .map(|(i, position)| {
+                let mut acceleration = Vector3::ORIGIN;
+                for (j, (other, mass)) in positions.iter().zip(masses).enumerate() {
+                    if i == j || *mass == 0.0 {
+                        continue;
+                    }
+                    let separation = *other - *position;
+                    let distance_squared = separation.dot(&separation);
+                    if distance_squared > 0.0 {
+                        acceleration = acceleration
+                            + separation
+                                * (GRAVITATIONAL_CONSTANT * mass
+                                    / (distance_squared * distance_squared.sqrt()));
+                    }
+                }
+                acceleration
+            })
+            .collect()
+    }
+
+    /// Advances the positions and velocities by one step using the fourth order Runge-Kutta method
+    fn runge_kutta_step(
+        &self,
+        positions: &[Vector3<Float>],
+        velocities: &[Vector3<Float>],
+        masses: &[Float],
+    ) -> (Vec<Vector3<Float>>, Vec<Vector3<Float>>) {
+        // Moves `base` along `derivative` for `time` hours
+        let advance = |base: &[Vector3<Float>], derivative: &[Vector3<Float>], time: Float| {
+            base.iter()
+                .zip(derivative)
+                .map(|(b, d)| *b + *d * time)
+                .collect::<Vec<_>>()
+        };
+        let half_step = self.step * 0.5;
+
+        let velocity_1 = velocities.to_vec();
+        let acceleration_1 = Self::get_accelerations(positions, masses);
+
+        let velocity_2 = advance(velocities, &acceleration_1, half_step);
+        let acceleration_2 =
+            Self::get_accelerations(&advance(positions, &velocity_1, half_step), masses);
+
+        let velocity_3 = advance(velocities, &acceleration_2, half_step);
+        let acceleration_3 =
+            Self::get_accelerations(&advance(positions, &velocity_2, half_step), masses);
+
+        let velocity_4 = advance(velocities, &acceleration_3, self.step);
+        let acceleration_4 =
+            Self::get_accelerations(&advance(positions, &velocity_3, self.step), masses);
+
+        // Weighted average of the four estimates
+        let weighted = |k1: &[Vector3<Float>],
+                        k2: &[Vector3<Float>],
+                        k3: &[Vector3<Float>],
+                        k4: &[Vector3<Float>]| {
+            (0..k1.len())
+                .map(|i| (k1[i] + (k2[i] + k3[i]) * 2.0 + k4[i]) / 6.0)
+                .collect::<Vec<_>>()
+        };
+
+        (
+            advance(
+                positions,
+                &weighted(&velocity_1, &velocity_2, &velocity_3, &velocity_4),
+                self.step,
+            ),
+            advance(
+                velocities,
+                &weighted(
+                    &acceleration_1,
+                    &acceleration_2,
+                    &acceleration_3,
+                    &acceleration_4,
+                ),
+                self.step,
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::Body,
+        dynamic::{fixed::Fixed, keplerian::Keplerian},
+    };
+
+    const STAR_MASS: Float = 1_000.0;
+
+    /// A star with a single planet, returns the root and the planet
+    fn get_system(planet_mass: Option<Float>, orbit: Keplerian) -> (Arc, Arc) {
+        let star = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        star.write().unwrap().set_mass(Some(STAR_MASS));
+        let planet = Body::new(Some(star.clone()), orbit);
+        planet.write().unwrap().set_mass(planet_mass);
+        Body::hydrate_all(&star, &None);
+        (star, planet)
+    }
+
+    /// Gets the first child of the root
+    fn get_planet(root: &Arc) -> Arc {
+        root.read().unwrap().get_children()[0].clone()
+    }
+
+    fn assert_follows_orbit(root: &Arc, orbit: &Keplerian, end_time: Float, tolerance: Float) {
+        let planet = get_planet(root);
+        let planet = planet.read().unwrap();
+        assert!(planet
+            .get_dynamic()
+            .as_any()
+            .downcast_ref::<Integrated>()
+            .is_some());
+
+        for i in 0_u8..=100 {
+            let time = Float::from(i) / 100.0 * end_time;
+            let expected = orbit.get_offset(time);
+            let real = planet.get_dynamic().get_offset(time);
+            assert!(
+                (real - expected).magnitude() < tolerance * expected.magnitude(),
+                "t={time}, expected {expected:?}, got {real:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_particle_follows_keplerian() {
+        let orbit = Keplerian::new(0.2, 100.0, 0.1, 0.2, 0.3, 0.4, STAR_MASS);
+        // Roughly two orbits
+        let end_time = 2_000.0;
+
+        for integrator in [Integrator::Leapfrog, Integrator::RungeKutta4] {
+            let (star, _) = get_system(None, orbit);
+            let integrated = NBody::new(2.0, integrator).integrate(&star, 0.0, end_time);
+            assert_follows_orbit(&integrated, &orbit, end_time, 1e-2);
+        }
+    }
+
+    #[test]
+    fn massive_bodies_orbit_each_other() {
+        const PLANET_MASS: Float = 250.0;
+        // Relative orbits only depend on the total mass
+        let orbit = Keplerian::new(0.1, 100.0, 0.0, 0.0, 0.0, 0.0, STAR_MASS + PLANET_MASS);
+        let end_time = 2_000.0;
+
+        let (star, _) = get_system(Some(PLANET_MASS), orbit);
+        let integrated = NBody::new(2.0, Integrator::RungeKutta4).integrate(&star, 0.0, end_time);
+        assert_follows_orbit(&integrated, &orbit, end_time, 1e-2);
+    }
+
+    #[test]
+    fn original_tree_is_unchanged() {
+        let orbit = Keplerian::new(0.2, 100.0, 0.1, 0.2, 0.3, 0.4, STAR_MASS);
+        let (star, planet) = get_system(Some(250.0), orbit);
+        let integrated = NBody::new(2.0, Integrator::Leapfrog).integrate(&star, 100.0, 200.0);
+
+        // Integrating twice starts from the same orbit
+        let again = NBody::new(2.0, Integrator::Leapfrog).integrate(&star, 100.0, 200.0);
+        let [planet_copy, planet_again] = [&integrated, &again].map(get_planet);
+        assert_eq!(
+            planet_copy.read().unwrap().get_dynamic().get_offset(150.0),
+            planet_again.read().unwrap().get_dynamic().get_offset(150.0)
+        );
+        assert!(planet
+            .read()
+            .unwrap()
+            .get_dynamic()
+            .as_any()
+            .downcast_ref::<Keplerian>()
+            .is_some());
+        assert!(serde_json::to_string(&*star.read().unwrap())
+            .unwrap()
+            .contains("Keplerian"));
+
+        // Outside of the integrated times the original orbit is used, rather than the ends of
+        // the table
+        let planet_copy = planet_copy.read().unwrap();
+        for time in [0.0, 50.0, 300.0] {
+            assert_eq!(
+                planet_copy.get_dynamic().get_offset(time),
+                orbit.get_offset(time)
+            );
+            assert_eq!(
+                planet_copy.get_dynamic().get_velocity(time),
+                orbit.get_velocity(time)
+            );
+        }
+        assert!(planet_copy.get_dynamic().get_velocity(150.0).magnitude() > 0.0);
+    }
+
+    #[test]
+    fn deserialize_defaults() {
+        let n_body: NBody = serde_json::from_str(r#"{"step":0.5}"#).unwrap();
+        assert_eq!(n_body, NBody::new(0.5, Integrator::Leapfrog));
+
+        let n_body: NBody =
+            serde_json::from_str(r#"{"step":0.5,"integrator":"RungeKutta4"}"#).unwrap();
+        assert_eq!(n_body, NBody::new(0.5, Integrator::RungeKutta4));
+    }
+}
@@ -0,0 +1,422 @@
+use std::{fmt::Display, path::PathBuf};
+
+use coordinates::prelude::*;
+use dyn_partial_eq::DynPartialEq;
+use serde::{Deserialize, Serialize};
+
+use crate::{csv, Float};
+
+use super::Dynamic;
+
+/// A dynamic that follows a precomputed table of offsets (an
+/// [ephemeris](https://en.wikipedia.org/wiki/Ephemeris)), e.g. the output of an external
+/// numerical integrator. Offsets between entries are found with [cubic Hermite
+/// interpolation](https://en.wikipedia.org/wiki/Cubic_Hermite_spline), and times outside the
+/// table use the nearest entry.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, DynPartialEq)]
+#[serde(try_from = "IntermediateTabulated", into = "IntermediateTabulated")]
+pub struct Tabulated {
+    /// Unit: hours.
+    ///
+    /// Definition: Strictly increasing times of each entry.
+    times: Vec<Float>,
+    /// Unit: light-seconds.
+    ///
+    /// Definition: The offset from the parent body at the matching time in [`Self::times`].
+    offsets: Vec<Vector3<Float>>,
+    /// The CSV file the table was loaded from, if any, as it was given. Used to avoid writing the
+    /// whole table when serializing.
+    source: Option<PathBuf>,
+}
+
+/// Errors that can occur while building a [`Tabulated`] dynamic
+#[derive(Debug)]
+pub enum TabulatedError {
+    /// The table has no entries
+    Empty,
+    /// The entry at the given index is not after the previous entry
+    Unordered(usize),
+    /// The time of the entry at the given index is not a finite number
+    InvalidTime(usize),
+    /// The CSV file could not be read
+    Io(std::io::Error),
+    /// The given line (starting at 1) of a CSV file could not be parsed
+    Parse(usize),
+}
+
+impl Display for TabulatedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Tabulated dynamic must have at least one entry"),
+            Self::Unordered(i) => write!(
+                f,
+                "Entry {i} of the tabulated dynamic is not after the previous entry"
+            ),
+            Self::InvalidTime(i) => write!(
+                f,
+                "Entry {i} of the tabulated dynamic does not have a finite time"
+            ),
+            Self::Io(e) => write!(f, "Could not read tabulated dynamic: {e}"),
+            Self::Parse(line) => write!(
+                f,
+                "Could not parse line {line} of the tabulated dynamic, expected `time,x,y,z`"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TabulatedError {}
+
+impl Tabulated {
+    /// Generates a new tabulated dynamic from `(time, offset)` entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no entries, or the times are not finite and strictly
+    /// increasing.
+    pub fn new(entries: Vec<(Float, Vector3<Float>)>) -> Result<Self, TabulatedError> {
+        if entries.is_empty() {
+            return Err(TabulatedError::Empty);
+        }
+        if let Some(i) = entries.iter().position(|(time, _)| !time.is_finite()) {
+            return Err(TabulatedError::InvalidTime(i));
+        }
+        if let Some(i) = entries.windows(2).position(|w| w[0].0 >= w[1].0) {
+            return Err(TabulatedError::Unordered(i + 1));
+        }
+
+        let (times, offsets) = entries.into_iter().unzip();
+        Ok(Self {
+            times,
+            offsets,
+            source: None,
+        })
+    }
+
+    /// Loads a tabulated dynamic from a CSV file with the columns `time,x,y,z`. Blank lines, lines
+    /// starting with `#`, and a single header row before the entries are ignored. Relative paths
+    /// are resolved against the directory given to [`csv::with_base_directory`], or the working
+    /// directory otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, a row cannot be parsed, or the entries are
+    /// not valid (see [`Self::new`]).
+    pub fn from_csv<T: Into<PathBuf>>(path: T) -> Result<Self, TabulatedError> {
+        let path = path.into();
+        let entries = csv::read_rows(&path, 4)
+            .map_err(|error| match error {
+                csv::ReadError::Io(e) => TabulatedError::Io(e),
+                csv::ReadError::Parse(line) => TabulatedError::Parse(line),
+            })?
+            .into_iter()
+            .map(|row| (row[0], Vector3::from((row[1], row[2], row[3]))))
+            .collect();
+
+        let mut result = Self::new(entries)?;
+        result.source = Some(path);
+        Ok(result)
+    }
+
+    /// Returns true if `time` is within the table, i.e. it is not clamped to an end
+    pub(crate) fn covers(&self, time: Float) -> bool {
+        self.times[0] <= time && time <= self.times[self.times.len() - 1]
+    }
+
+    /// Gets the entries either side of `time`, the time between them, and how far `time` is
+    /// between them (from 0 to 1). Returns [`None`] if `time` is outside the table.
+    fn get_segment(&self, time: Float) -> Option<(usize, usize, Float, Float)> {
+        // Index of the first entry after `time`
+        let next = self.times.partition_point(|t| *t <= time);
+        if next == 0 || next == self.times.len() {
+            return None;
+        }
+
+        let previous = next - 1;
+        let step = self.times[next] - self.times[previous];
+        Some((previous, next, step, (time - self.times[previous]) / step))
+    }
+
+    /// Gets the index of the entry at the closest end of the table to `time`
+    fn get_nearest(&self, time: Float) -> usize {
+        if time < self.times[0] {
+            0
+        } else {
+            self.times.len() - 1
+        }
+    }
+
+    /// Gets the gradient of the straight line between an entry and the next entry
+    fn get_secant(&self, index: usize) -> Vector3<Float> {
+        (self.offsets[index + 1] - self.offsets[index])
+            / (self.times[index + 1] - self.times[index])
+    }
+
+    /// Gets the rate of change of the offset at the given entry, from the derivative of the
+    /// quadratic through it and its neighbouring entries
+    fn get_tangent(&self, index: usize) -> Vector3<Float> {
+        let last = self.times.len() - 1;
+        match last {
+            0 => return Vector3::ORIGIN,
+            1 => return self.get_secant(0),
+            _ => (),
+        }
+
+        // The three entries the quadratic goes through, shifted inwards at the ends of the table
+        let middle = index.clamp(1, last - 1);
+        let (before, after) = (self.get_secant(middle - 1), self.get_secant(middle));
+        let (before_step, after_step) = (
+            self.times[middle] - self.times[middle - 1],
+            self.times[middle + 1] - self.times[middle],
+        );
+        let total_step = before_step + after_step;
+
+        if index == 0 {
+            before + (before - after) * (before_step / total_step)
+        } else if index == last {
+            after + (after - before) * (after_step / total_step)
+        } else {
+            before * (after_step / total_step) + after * (before_step / total_step)
+        }
+    }
+}
+
+#[typetag::serde]
+impl Dynamic for Tabulated {
+    /// Returns the interpolated offset from the parent body at a given time.
+    fn get_offset(&self, time: Float) -> Vector3<Float> {
+        let Some((previous, next, step, s)) = self.get_segment(time) else {
+            return self.offsets[self.get_nearest(time)];
+        };
+        let (s2, s3) = (s * s, s * s * s);
+
+        // Hermite basis functions
+        self.offsets[previous] * (2.0 * s3 - 3.0 * s2 + 1.0)
+            + self.get_tangent(previous) * ((s3 - 2.0 * s2 + s) * step)
+            + self.offsets[next] * (3.0 * s2 - 2.0 * s3)
+            + self.get_tangent(next) * ((s3 - s2) * step)
+    }
+
+    /// Returns the derivative of the interpolated offset at a given time, zero outside the table.
+    fn get_velocity(&self, time: Float) -> Vector3<Float> {
+        let Some((previous, next, step, s)) = self.get_segment(time) else {
+            return Vector3::ORIGIN;
+        };
+        let s2 = s * s;
+
+        // Derivatives of the Hermite basis functions
+        (self.offsets[previous] - self.offsets[next]) * ((6.0 * s2 - 6.0 * s) / step)
+            + self.get_tangent(previous) * (3.0 * s2 - 4.0 * s + 1.0)
+            + self.get_tangent(next) * (3.0 * s2 - 2.0 * s)
+    }
+}
+
+/// Serialized form of [`Tabulated`], either an inline table or a path to a CSV file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", untagged)]
+enum IntermediateTabulated {
+    /// Path to a CSV file with the columns `time,x,y,z`, see [`Tabulated::from_csv`] for how
+    /// relative paths are resolved
+    File {
+        /// Path to the CSV file
+        file: PathBuf,
+    },
+    /// Inline table
+    Entries {
+        /// `[time, x, y, z]` entries
+        entries: Vec<[Float; 4]>,
+    },
+}
+
+impl TryFrom<IntermediateTabulated> for Tabulated {
+    type Error = TabulatedError;
+
+    fn try_from(value: IntermediateTabulated) -> Result<Self, Self::Error> {
+        match value {
+            IntermediateTabulated::File { file } => Self::from_csv(file),
+            IntermediateTabulated::Entries { entries } => Self::new(
+                entries
+                    .into_iter()
+                    .map(|[time, x, y, z]| (time, Vector3::from((x, y, z))))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<Tabulated> for IntermediateTabulated {
+    fn from(value: Tabulated) -> Self {
+        if let Some(file) = value.source {
+            IntermediateTabulated::File { file }
+        } else {
+            IntermediateTabulated::Entries {
+                entries: value
+                    .times
+                    .into_iter()
+                    .zip(value.offsets)
+                    .map(|(time, offset)| [time, offset.x, offset.y, offset.z])
+                    .collect(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        body::{Arc, Body},
+        consts::float,
+    };
+
+    /// Samples a unit circle every `step` hours, with a period of tau hours
+    fn get_circle(step: Float) -> Tabulated {
+        Tabulated::new(
+            (0_u8..=100)
+                .map(|i| {
+                    let time = Float::from(i) * step;
+                    let (sin, cos) = time.sin_cos();
+                    (time, Vector3::from((cos, sin, 0.0)))
+                })
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn exact_at_entries() {
+        let circle = get_circle(0.5);
+        for (time, offset) in circle.times.iter().zip(&circle.offsets) {
+            assert_eq!(circle.get_offset(*time), *offset);
+        }
+    }
+
+    #[test]
+    fn interpolates_between_entries() {
+        // 24 samples per orbit
+        let circle = get_circle(float::TAU / 24.0);
+        for i in 0_u8..100 {
+            let time = Float::from(i) * 0.25;
+            let real = circle.get_offset(time);
+            let (sin, cos) = time.sin_cos();
+
+            assert_float_absolute_eq!(real.x, cos, 1e-3);
+            assert_float_absolute_eq!(real.y, sin, 1e-3);
+        }
+    }
+
+    #[test]
+    fn velocity_between_entries() {
+        let circle = get_circle(float::TAU / 24.0);
+        for i in 0_u8..100 {
+            let time = Float::from(i) * 0.25;
+            let real = circle.get_velocity(time);
+            let (sin, cos) = time.sin_cos();
+
+            assert_float_absolute_eq!(real.x, -sin, 3e-2);
+            assert_float_absolute_eq!(real.y, cos, 3e-2);
+        }
+    }
+
+    #[test]
+    fn clamps_outside_table() {
+        let circle = get_circle(0.5);
+        assert_eq!(circle.get_offset(-10.0), circle.offsets[0]);
+        assert_eq!(circle.get_offset(1e6), *circle.offsets.last().unwrap());
+        assert_eq!(circle.get_velocity(-10.0), Vector3::ORIGIN);
+    }
+
+    #[test]
+    fn invalid_tables() {
+        assert!(matches!(Tabulated::new(vec![]), Err(TabulatedError::Empty)));
+        assert!(matches!(
+            Tabulated::new(vec![(1.0, Vector3::ORIGIN), (1.0, Vector3::UP)]),
+            Err(TabulatedError::Unordered(1))
+        ));
+        assert!(matches!(
+            Tabulated::new(vec![(0.0, Vector3::ORIGIN), (Float::NAN, Vector3::UP)]),
+            Err(TabulatedError::InvalidTime(1))
+        ));
+    }
+
+    #[test]
+    fn load_from_csv() {
+        let directory = crate::testing::get_temp_directory("tabulated");
+        let path = directory.join("ephemeris.csv");
+        std::fs::write(
+            &path,
+            "# Test ephemeris\ntime,x,y,z\n0.0, 1.0, 0.0, 0.0\n1.0, 0.0, 1.0, 0.0\n\n2.0,-1.0,0.0,0.0\n",
+        )
+        .unwrap();
+
+        let json = format!(
+            r#"{{
+                "name": "Star",
+                "dynamic": {{ "Fixed": {{ "x": 0.0, "y": 0.0, "z": 0.0 }} }},
+                "children": [
+                    {{ "name": "File", "dynamic": {{ "Tabulated": {{ "file": {:?} }} }} }},
+                    {{ "name": "Inline", "dynamic": {{ "Tabulated": {{ "entries": [[0.0, 1.0, 0.0, 0.0], [1.0, 0.0, 1.0, 0.0], [2.0, -1.0, 0.0, 0.0]] }} }} }}
+                ]
+            }}"#,
+            path.to_str().unwrap()
+        );
+
+        let root: Arc = serde_json::from_str::<Body>(&json).unwrap().into();
+        let root = root.read().unwrap();
+        let [file, inline] = [0, 1].map(|i| {
+            root.get_children()[i]
+                .read()
+                .unwrap()
+                .get_dynamic()
+                .as_any()
+                .downcast_ref::<Tabulated>()
+                .unwrap()
+                .clone()
+        });
+
+        assert_eq!(file.times, inline.times);
+        assert_eq!(file.offsets, inline.offsets);
+        assert_eq!(file.get_offset(1.0), Vector3::from((0.0, 1.0, 0.0)));
+
+        // Tables loaded from files keep referencing the file
+        assert!(serde_json::to_string(&file).unwrap().contains("file"));
+        assert!(serde_json::to_string(&inline).unwrap().contains("entries"));
+
+        std::fs::write(&path, "0.0,1.0,0.0,0.0\nnot,a,number,here\n").unwrap();
+        assert!(matches!(
+            Tabulated::from_csv(&path),
+            Err(TabulatedError::Parse(2))
+        ));
+
+        // Only one header row
+        std::fs::write(&path, "time,x,y,z\nnot,a,number,here\n0.0,1.0,0.0,0.0\n").unwrap();
+        assert!(matches!(
+            Tabulated::from_csv(&path),
+            Err(TabulatedError::Parse(2))
+        ));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn relative_csv_paths() {
+        let directory = crate::testing::get_temp_directory("tabulated-relative");
+        std::fs::write(directory.join("ephemeris.csv"), "0.0,1.0,0.0,0.0\n").unwrap();
+
+        // Resolved against the base directory, e.g. the directory of the universe file
+        let json = r#"{ "file": "ephemeris.csv" }"#;
+        assert!(serde_json::from_str::<Tabulated>(json).is_err());
+        let table =
+            crate::csv::with_base_directory(&directory, || serde_json::from_str::<Tabulated>(json))
+                .unwrap();
+        assert_eq!(table.get_offset(0.0), Vector3::from((1.0, 0.0, 0.0)));
+
+        // The path is written back as it was given
+        assert_eq!(
+            serde_json::to_string(&table).unwrap(),
+            json.replace(' ', "")
+        );
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
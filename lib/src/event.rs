@@ -0,0 +1,309 @@
+use crate::Float;
+
+/// How far apart two things are at some time, compared to the separations where they start to
+/// overlap, e.g. two bodies in the sky during an eclipse
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overlap {
+    /// Distance between the centres, e.g. the angle between two bodies in the sky
+    pub separation: Float,
+    /// Separations at which each stage of the event begins, from the outermost (first contact) to
+    /// the innermost
+    pub thresholds: Vec<Float>,
+}
+
+/// An event found by [`search`], where a separation dips below one or more thresholds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    /// Unit: hours
+    ///
+    /// Definition: Time of greatest overlap, i.e. when the separation is furthest inside the
+    /// outermost threshold
+    pub maximum: Float,
+    /// Unit: hours
+    ///
+    /// Definition: `(ingress, egress)` times that the separation crosses each threshold, in the
+    /// same order as [`Overlap::thresholds`]. Times are `None` if the threshold is never crossed,
+    /// or is crossed outside of the searched times.
+    pub contacts: Vec<(Option<Float>, Option<Float>)>,
+}
+
+impl Event {
+    /// Gets the time the event starts, i.e. the separation crosses the outermost threshold
+    #[must_use]
+    pub fn get_first_contact(&self) -> Option<Float> {
+        self.contacts.first()?.0
+    }
+
+    /// Gets the time the separation crosses the second threshold on the way in, e.g. when an
+    /// eclipse becomes total
+    #[must_use]
+    pub fn get_second_contact(&self) -> Option<Float> {
+        self.contacts.get(1)?.0
+    }
+
+    /// Gets the time the separation crosses the second threshold on the way out, e.g. when an
+    /// eclipse stops being total
+    #[must_use]
+    pub fn get_third_contact(&self) -> Option<Float> {
+        self.contacts.get(1)?.1
+    }
+
+    /// Gets the time the event ends, i.e. the separation crosses the outermost threshold
+    #[must_use]
+    pub fn get_fourth_contact(&self) -> Option<Float> {
+        self.contacts.first()?.1
+    }
+}
+
+/// Searches for events between the sample `times` (which must be increasing), refining the
+/// contact times and maximum to within `tolerance` hours. Events that start and end between two
+/// samples are found as long as the separation relative to the outermost threshold dips to a
+/// minimum around the samples, i.e. the events are not much shorter than the time between
+/// samples. `overlap` returns `None` when there can't be an event, e.g. a body is below the
+/// horizon.
+pub fn search<F>(times: &[Float], tolerance: Float, overlap: F) -> Vec<Event>
+where
+    F: Fn(Float) -> Option<Overlap>,
+{
+    // How far outside the given threshold the separation is, events happen when this is negative
+    let distance = |threshold: usize, time: Float| {
+        overlap(time)
+            .and_then(|o| Some(o.separation - o.thresholds.get(threshold)?))
+            .unwrap_or(Float::INFINITY)
+    };
+    let outer = |time| distance(0, time);
+
+    let samples: Vec<Float> = times.iter().map(|time| outer(*time)).collect();
+    let mut events: Vec<Event> = Vec::new();
+    for i in 0..samples.len() {
+        let previous = i.saturating_sub(1);
+        let next = (i + 1).min(samples.len() - 1);
+        // Only look around local minima, where the separation may dip inside the threshold
+        if samples[i].is_infinite() || samples[i] > samples[previous] || samples[i] > samples[next]
+        {
+            continue;
+        }
+
+        let refined = find_minimum(outer, times[previous], times[next], tolerance);
+        let maximum = if outer(refined) <= samples[i] {
+            refined
+        } else {
+            times[i]
+        };
+        if outer(maximum) >= 0.0 {
+            continue;
+        }
+
+        // Several minima can be found in a single long event
+        if events.last().is_some_and(|event| {
+            event
+                .get_fourth_contact()
+                .is_none_or(|fourth| maximum <= fourth)
+        }) {
+            continue;
+        }
+
+        let thresholds = overlap(maximum).map_or(0, |o| o.thresholds.len());
+        let contacts = (0..thresholds)
+            .map(|threshold| {
+                let distance = |time| distance(threshold, time);
+                if distance(maximum) >= 0.0 {
+                    return (None, None);
+                }
+                (
+                    find_crossing(
+                        distance,
+                        maximum,
+                        times.iter().rev().filter(|time| **time < maximum),
+                        tolerance,
+                    ),
+                    find_crossing(
+                        distance,
+                        maximum,
+                        times.iter().filter(|time| **time > maximum),
+                        tolerance,
+                    ),
+                )
+            })
+            .collect();
+        events.push(Event { maximum, contacts });
+    }
+
+    events
+}
+
+/// Steps away from `inside` (where `distance` is negative) through `times`, and finds the first
+/// time that `distance` stops being negative
+fn find_crossing<'a, F: Fn(Float) -> Float>(
+    distance: F,
+    inside: Float,
+    times: impl Iterator<Item = &'a Float>,
+    tolerance: Float,
+) -> Option<Float> {
+    let mut last = inside;
+    for time in times {
+        if distance(*time) >= 0.0 {
+            return Some(find_root(distance, last, *time, tolerance));
+        }
+        last = *time;
+    }
+    None
+}
+
+/// Finds a value between `a` and `b` where `f` changes sign to within `tolerance`, using
+/// [bisection](https://en.wikipedia.org/wiki/Bisection_method). `f(a)` and `f(b)` should have
+/// opposite signs, otherwise one of the ends is returned.
+pub fn find_root<F: Fn(Float) -> Float>(
+    f: F,
+    mut a: Float,
+    mut b: Float,
+    tolerance: Float,
+) -> Float {
+    let a_is_negative = f(a) < 0.0;
+    while (b - a).abs() > tolerance {
+        let middle = (a + b) / 2.0;
+        // Out of precision
+        if middle == a || middle == b {
+            break;
+        }
+
+        if (f(middle) < 0.0) == a_is_negative {
+            a = middle;
+        } else {
+            b = middle;
+        }
+    }
+    (a + b) / 2.0
+}
+
+/// Finds a value between `a` and `b` where `f` is smallest to within `tolerance`, using a
+/// [golden-section search](https://en.wikipedia.org/wiki/Golden-section_search). If there are
+/// several local minima, any one of them may be returned.
+pub fn find_minimum<F: Fn(Float) -> Float>(
+    f: F,
+    mut a: Float,
+    mut b: Float,
+    tolerance: Float,
+) -> Float {
+    // 1 / φ, the ratio each step shrinks the search by. Computed rather than written out so it has
+    // the right precision for both f32 and f64
+    let inverse_phi = (Float::sqrt(5.0) - 1.0) / 2.0;
+
+    let mut c = b - (b - a) * inverse_phi;
+    let mut d = a + (b - a) * inverse_phi;
+    let (mut fc, mut fd) = (f(c), f(d));
+    while (b - a).abs() > tolerance {
+        if fc < fd {
+            b = d;
+            d = c;
+            fd = fc;
+            c = b - (b - a) * inverse_phi;
+            fc = f(c);
+        } else {
+            a = c;
+            c = d;
+            fc = fd;
+            d = a + (b - a) * inverse_phi;
+            fd = f(d);
+        }
+
+        // Out of precision
+        if c >= d {
+            break;
+        }
+    }
+    (a + b) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_minimum, find_root, search, Overlap};
+    use crate::{consts::float, Float};
+
+    #[test]
+    fn roots_and_minima() {
+        assert_float_absolute_eq!(
+            find_root(|x| x * x - 2.0, 0.0, 2.0, 1e-5),
+            float::SQRT_2,
+            1e-4
+        );
+        assert_float_absolute_eq!(
+            find_root(|x| 2.0 - x * x, 0.0, 2.0, 1e-5),
+            float::SQRT_2,
+            1e-4
+        );
+        assert_float_absolute_eq!(
+            find_minimum(|x| (x - 0.3) * (x - 0.3), -1.0, 2.0, 1e-5),
+            0.3,
+            1e-4
+        );
+    }
+
+    /// A body moving through another at one unit per hour, closest at `centre`
+    fn passing(centre: Float) -> impl Fn(Float) -> Option<Overlap> {
+        move |time| {
+            Some(Overlap {
+                separation: (time - centre).abs().hypot(0.1),
+                thresholds: vec![1.0, 0.5],
+            })
+        }
+    }
+
+    #[test]
+    fn contacts_are_refined() {
+        let times: Vec<Float> = (0..10).map(|t| t as Float).collect();
+        let events = search(&times, 1e-5, passing(4.3));
+        assert_eq!(events.len(), 1);
+
+        let event = &events[0];
+        let half_width = |threshold: Float| (threshold * threshold - 0.01).sqrt();
+        assert_float_absolute_eq!(event.maximum, 4.3, 1e-3);
+        assert_float_absolute_eq!(
+            event.get_first_contact().unwrap(),
+            4.3 - half_width(1.0),
+            1e-4
+        );
+        assert_float_absolute_eq!(
+            event.get_second_contact().unwrap(),
+            4.3 - half_width(0.5),
+            1e-4
+        );
+        assert_float_absolute_eq!(
+            event.get_third_contact().unwrap(),
+            4.3 + half_width(0.5),
+            1e-4
+        );
+        assert_float_absolute_eq!(
+            event.get_fourth_contact().unwrap(),
+            4.3 + half_width(1.0),
+            1e-4
+        );
+    }
+
+    #[test]
+    fn short_events_between_samples() {
+        // The event lasts 1.4 hours, and is never inside the threshold at a sample
+        let times: Vec<Float> = (0..10).map(|t| (t * 2) as Float).collect();
+        let events = search(&times, 1e-5, |time| {
+            Some(Overlap {
+                separation: (time - 7.0).abs(),
+                thresholds: vec![0.7],
+            })
+        });
+        assert_eq!(events.len(), 1);
+        assert_float_absolute_eq!(events[0].maximum, 7.0, 1e-3);
+        assert_float_absolute_eq!(events[0].get_first_contact().unwrap(), 6.3, 1e-4);
+        assert_float_absolute_eq!(events[0].get_fourth_contact().unwrap(), 7.7, 1e-4);
+        assert!(events[0].get_second_contact().is_none());
+
+        // Events that run off the end of the search have missing contacts
+        let events = search(&times[..4], 1e-5, passing(6.0));
+        assert_eq!(events.len(), 1);
+        assert!(events[0].get_first_contact().is_some());
+        assert!(events[0].get_fourth_contact().is_none());
+
+        // No events
+        assert!(search(&times, 1e-5, |_| None).is_empty());
+        assert!(search(&times, 1e-5, passing(40.0)).is_empty());
+    }
+}
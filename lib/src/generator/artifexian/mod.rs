@@ -29,6 +29,10 @@ pub struct Artifexian {
     /// Number of stars to generate
     #[builder(default = 1_000_000)]
     star_count: usize,
+    /// Percentage of stars that are generated as [binary
+    /// systems](https://en.wikipedia.org/wiki/Binary_star), values above 100 are treated as 100.
+    #[builder(default = 0)]
+    binary_star_percentage: u8,
 }
 
 impl Generator for Artifexian {
@@ -118,7 +122,16 @@ impl Generator for Artifexian {
                 star
             };
 
-            if let (_, Some(observer)) = star.to_body(rng, &root) {
+            let (_, observer) = if self.binary_star_percentage > 0
+                && rng.gen_ratio(u32::from(self.binary_star_percentage.min(100)), 100)
+            {
+                let companion = MainSequenceStar::new(rng);
+                star.to_binary_body(rng, &root, &companion)
+            } else {
+                star.to_body(rng, &root)
+            };
+
+            if let Some(observer) = observer {
                 observatories.push(observer);
             }
         }
@@ -156,6 +169,7 @@ mod test {
     use rand::SeedableRng;
 
     use super::*;
+    use crate::dynamic::Dynamic;
 
     //#[ignore = "long running"]
     #[test]
@@ -182,4 +196,60 @@ mod test {
         let mut rng = rand::rngs::mock::StepRng::new(INC + (INC >> 8) + (INC >> 16), INC);
         let _ = generator.generate(&mut rng);
     }
+
+    #[test]
+    fn binary_stars() {
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(42_123);
+        let (root, observatories) = ArtifexianBuilder::default()
+            .star_count(200)
+            .binary_star_percentage(100)
+            .build()
+            .unwrap()
+            .generate(&mut rng);
+
+        // Habitable systems still get observatories
+        assert_eq!(observatories.len(), 2);
+
+        for barycentre in root.read().unwrap().get_children() {
+            let barycentre = barycentre.read().unwrap();
+            let stars = barycentre.get_children();
+            assert_eq!(stars.len(), 2);
+
+            let [primary, secondary] = [0, 1].map(|i| {
+                let star = stars[i].read().unwrap();
+                *star
+                    .get_dynamic()
+                    .as_any()
+                    .downcast_ref::<dynamic::barycentric::Barycentric>()
+                    .unwrap()
+            });
+            assert_eq!(primary.get_orbit(), secondary.get_orbit());
+            for time in [0.0, 1e5, 1e7] {
+                let (a, b) = (primary.get_offset(time), secondary.get_offset(time));
+                assert!(a.x * b.x <= 0.0 && a.y * b.y <= 0.0 && a.z * b.z <= 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn moons_are_tidally_locked() {
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(42_123);
+        let (root, _) = ArtifexianBuilder::default()
+            .star_count(200)
+            .build()
+            .unwrap()
+            .generate(&mut rng);
+
+        let mut moon_count = 0;
+        for star in root.read().unwrap().get_children() {
+            for planet in star.read().unwrap().get_children() {
+                for moon in planet.read().unwrap().get_children() {
+                    let moon = moon.read().unwrap();
+                    assert!(moon.rotation.as_ref().unwrap().is_tidally_locked());
+                    moon_count += 1;
+                }
+            }
+        }
+        assert!(moon_count > 0);
+    }
 }
@@ -1,4 +1,4 @@
-use super::{dynamic, float, random_angle, Arc, Body, Float, Planet, PlanetType};
+use super::{dynamic, float, random_angle, Arc, Body, Float, Planet, PlanetType, Rotating};
 
 /// Type of moon
 #[derive(Debug, Clone)]
@@ -13,6 +13,7 @@ enum MoonType {
     MinorIcy,
 }
 
+/// A moon that orbits a planet
 #[derive(Debug, Clone)]
 pub(super) struct Moon {
     /// Radius of the moon in ls
@@ -204,19 +205,21 @@ impl Moon {
                 )
             }
         };
-        Body::new(
-            Some(parent_body.clone()),
-            dynamic::keplerian::Keplerian::new(
-                eccentricity,
-                self.semi_major_axis,
-                inclination + parent.north_pole.polar_angle,
-                parent.north_pole.azimuthal_angle
-                    + float::FRAC_PI_2
-                    + rng.gen_range(-10.0 as Float..10.0).to_radians(),
-                random_angle(rng),
-                random_angle(rng),
-                parent.mass,
-            ),
-        )
+        let orbit = dynamic::keplerian::Keplerian::new(
+            eccentricity,
+            self.semi_major_axis,
+            inclination + parent.north_pole.polar_angle,
+            parent.north_pole.azimuthal_angle
+                + float::FRAC_PI_2
+                + rng.gen_range(-10.0 as Float..10.0).to_radians(),
+            random_angle(rng),
+            random_angle(rng),
+            parent.mass,
+        );
+
+        let body = Body::new(Some(parent_body.clone()), orbit);
+        // Moons are close enough to their planets to be tidally locked
+        body.write().unwrap().rotation = Rotating::new_tidally_locked(&orbit, 0.0);
+        body
     }
 }
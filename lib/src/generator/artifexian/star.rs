@@ -1,6 +1,6 @@
 use super::{
-    au_to_ls, dynamic, float, random_angle, solar_masses_to_jupiter_masses, Arc, Body, Cylindrical,
-    Float, Planet, Range, Spherical,
+    au_to_ls, dynamic, float, keplerian, random_angle, solar_masses_to_jupiter_masses, Arc, Body,
+    Cylindrical, Float, Planet, Range, Spherical, Vector3,
 };
 
 /// A star that can have bodies that orbit it
@@ -68,12 +68,8 @@ impl MainSequenceStar {
         maximum * 1.029e8
     }
 
-    /// Convert this star to a body to add to the body tree
-    pub(super) fn to_body<G: rand::Rng>(
-        &self,
-        rng: &mut G,
-        root: &Arc,
-    ) -> (Arc, Option<crate::body::observatory::Observatory>) {
+    /// Generates a location for a star (or star system) in a spiral galaxy
+    fn get_galactic_location<G: rand::Rng>(rng: &mut G) -> Vector3<Float> {
         const WIDTH_OF_MILKY_WAY: Float = 3e12;
 
         let d = rand_distr::Pert::new(-1.0, 1.0, 0.0).unwrap();
@@ -93,12 +89,66 @@ impl MainSequenceStar {
         } else {
             random_angle(rng)
         };
+
+        Cylindrical::new(radius, height, theta).into()
+    }
+
+    /// Convert this star to a body to add to the body tree
+    pub(super) fn to_body<G: rand::Rng>(
+        &self,
+        rng: &mut G,
+        root: &Arc,
+    ) -> (Arc, Option<crate::body::observatory::Observatory>) {
+        let location = Self::get_galactic_location(rng);
         // Use fixed as a performance saver since their periods would be on the order of millions
         // of years
-        let b = Body::new(
-            Some(root.clone()),
-            dynamic::fixed::Fixed(Cylindrical::new(radius, height, theta).into()),
+        self.to_body_with_dynamic(rng, root, dynamic::fixed::Fixed(location))
+    }
+
+    /// Convert this star and a companion star to a binary system to add to the body tree. Planets
+    /// orbit this star (the primary), and the companion is placed far enough away to not disrupt
+    /// them.
+    ///
+    /// # Returns
+    ///
+    /// The barycentre of the system, and an observatory if a habitable planet was generated.
+    pub(super) fn to_binary_body<G: rand::Rng>(
+        &self,
+        rng: &mut G,
+        root: &Arc,
+        companion: &Self,
+    ) -> (Arc, Option<crate::body::observatory::Observatory>) {
+        let location = Self::get_galactic_location(rng);
+        let barycentre = Body::new(Some(root.clone()), dynamic::fixed::Fixed(location));
+
+        let separation =
+            self.planetary_zone.end.max(companion.planetary_zone.end) * rng.gen_range(4.0..20.0);
+        let orbit = keplerian::Keplerian::new(
+            rng.gen_range(0.0..0.5),
+            separation,
+            rng.gen_range(0.0..float::PI),
+            random_angle(rng),
+            random_angle(rng),
+            random_angle(rng),
+            self.mass + companion.mass,
         );
+        let [primary, secondary] =
+            dynamic::barycentric::Barycentric::new_binary(orbit, self.mass, companion.mass);
+
+        let (_, observatory) = self.to_body_with_dynamic(rng, &barycentre, primary);
+        let _ = companion.to_body_with_dynamic(rng, &barycentre, secondary);
+
+        (barycentre, observatory)
+    }
+
+    /// Convert this star to a body that follows the given dynamic
+    fn to_body_with_dynamic<G: rand::Rng, D: dynamic::Dynamic + 'static>(
+        &self,
+        rng: &mut G,
+        parent: &Arc,
+        dynamic: D,
+    ) -> (Arc, Option<crate::body::observatory::Observatory>) {
+        let b = Body::new(Some(parent.clone()), dynamic);
 
         let mut observatory = None;
         // Add planets to this body
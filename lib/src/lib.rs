@@ -11,8 +11,14 @@ pub mod body;
 /// Physical constants for the simulation, e.g. The Gravitational Constant, Pi, and Tau.
 #[allow(clippy::excessive_precision)] // Constants should work with up to f128 precision
 pub mod consts;
+/// Reads CSV files that bodies and observatories load their data from, e.g. tables of offsets and
+/// outlines of the horizon.
+pub mod csv;
 /// Structs that model the orbits that bodies can follow.
 pub mod dynamic;
+/// Searches for events, such as eclipses, that happen between observations and finds when they
+/// start and end.
+pub mod event;
 /// Objects that assist in outputting data to various types, e.g. HTML canvas, SVG, etc.
 pub mod output;
 /// A helper [facade](https://en.wikipedia.org/wiki/Facade_pattern) that takes simulation times and
@@ -55,6 +61,23 @@ pub mod testing {
 
     pub const DEFAULT_SEED: u128 = 0x064B_DEAF_BEEF_CAFE_064B_DEAF_BEEF_CAFE;
 
+    /// Gets an empty directory for a test to write files to. The name includes the process ID,
+    /// so test runs at the same time (e.g. with f32 and f64) don't share files, and anything left
+    /// behind by an earlier failed run is removed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the directory can't be created
+    #[must_use]
+    pub fn get_temp_directory(name: &str) -> std::path::PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("astrograph-{name}-{}", std::process::id()));
+        // Usually doesn't exist
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
     /// Generates an example [`crate::body::Body`] tree from a seed, at the moment this will only
     /// generate a tree with five ancestors of the observing body, the observing body, nine
     /// children of the observing body, and 9 children of those children (81 descendants in total)
@@ -2,6 +2,7 @@ use std::ops::Div;
 
 use coordinates::{prelude::Spherical, traits::Positional};
 
+use super::{EclipseKind, Occlusion};
 use crate::{body::Arc, consts::float, Float, LocalObservation};
 
 /// Grid applied to ovservations to speed up eclipse detection
@@ -46,8 +47,8 @@ impl CollisionGrid {
         Self { body_grid }
     }
 
-    /// Returns the magnitude of any eclipses if there is any
-    pub fn collisions(&self, near_point: &LocalObservation) -> Vec<(Arc, Float)> {
+    /// Returns the bodies that the near point is in front of, and how it covers them
+    pub fn collisions(&self, near_point: &LocalObservation) -> Vec<(Arc, Occlusion)> {
         // 7 or 18 faces that are adjacent to the face this point is on
         // len = 18 when the face is on the north or south pole region and many edges join on the z
         // axis
@@ -78,8 +79,23 @@ impl CollisionGrid {
             points
                 .into_iter()
                 .filter_map(|(angular_radius, loc, b)| {
-                    Self::check_collision(&near_point_diameter, &(angular_radius, *loc))
-                        .map(|mag| (b.clone(), mag))
+                    let magnitude =
+                        Self::check_collision(&near_point_diameter, &(angular_radius, *loc))?;
+                    let separation = near_point_diameter.1.angle_to(loc);
+                    Some((
+                        b.clone(),
+                        Occlusion {
+                            kind: EclipseKind::classify(
+                                near_point_diameter.0,
+                                angular_radius,
+                                separation,
+                            )?,
+                            magnitude,
+                            near_angular_radius: near_point_diameter.0,
+                            far_angular_radius: angular_radius,
+                            separation,
+                        },
+                    ))
                 })
                 .collect()
         } else {
@@ -88,7 +104,7 @@ impl CollisionGrid {
     }
 
     /// See if the near body is in front of the far body
-    fn check_collision(
+    pub(super) fn check_collision(
         near_point: &(Float, Spherical<Float>),
         far_point: &(Float, Spherical<Float>),
     ) -> Option<Float> {
@@ -118,7 +134,7 @@ impl CollisionGrid {
     fn get_face_id(loc: &Spherical<Float>) -> usize {
         let layer_count = (loc.polar_angle / float::FRAC_PI_8).floor() as usize;
         let x_count = ((loc.azimuthal_angle
-            - if layer_count % 2 != 0 {
+            - if !layer_count.is_multiple_of(2) {
                 float::FRAC_PI_8 / 2.0
             } else {
                 0.0
@@ -150,7 +166,7 @@ impl CollisionGrid {
         } else if row_number == Self::ROWS_PER_SPHERE - 1 {
             // South pole adjacency rules
 
-            if id % Self::CELLS_PER_ROW == 0 {
+            if id.is_multiple_of(Self::CELLS_PER_ROW) {
                 [id - Self::CELLS_PER_ROW, id - 1]
             } else {
                 // Previous row
@@ -162,9 +178,9 @@ impl CollisionGrid {
                 (Self::ROWS_PER_SPHERE - 1) * Self::CELLS_PER_ROW..(Self::NUMBER_OF_CELLS),
             )
             .collect()
-        } else if row_number % 2 != 0 {
+        } else if !row_number.is_multiple_of(2) {
             // Middle latitude adjacency rules (odd row)
-            if id % Self::CELLS_PER_ROW == 0 {
+            if id.is_multiple_of(Self::CELLS_PER_ROW) {
                 vec![
                     // Previous row
                     id - Self::CELLS_PER_ROW,
@@ -206,7 +222,7 @@ impl CollisionGrid {
             }
         } else {
             // Middle latitude adjacency rules (even row)
-            if id % Self::CELLS_PER_ROW == 0 {
+            if id.is_multiple_of(Self::CELLS_PER_ROW) {
                 vec![
                     // Previous row
                     id - Self::CELLS_PER_ROW,
@@ -1,30 +1,382 @@
 use std::{
     collections::HashMap,
+    fmt::Display,
     fs::OpenOptions,
     io::Write,
     sync::{Arc, RwLock},
 };
 
 use collision_check::CollisionGrid;
-use coordinates::prelude::Spherical;
+use coordinates::{prelude::Spherical, traits::Positional};
+use serde::Serialize;
 
-use crate::{output::Output, Float};
+use crate::{
+    body::{observatory::Observatory, Body},
+    event::{self, Event, Overlap},
+    output::Output,
+    Float, LocalObservation,
+};
 
 /// Provides a struct that speeds up eclipse checks
 mod collision_check;
+/// Provides structured, machine-readable records of eclipses
+pub mod record;
+/// Provides checks for bodies passing through the shadows of other bodies
+mod shadow;
+
+use record::{EclipseRecord, LogFormat};
 
 #[derive(Clone, Debug, Default)]
 pub struct Logger {
     /// List of eclipses that have been observed
     eclipse_log: Arc<RwLock<HashMap<Arc<std::path::Path>, Vec<String>>>>,
+    /// Records of eclipses that have been observed, for structured formats
+    records: Arc<RwLock<HashMap<Arc<std::path::Path>, Vec<EclipseRecord>>>>,
+    /// How eclipses are written
+    format: LogFormat,
+}
+
+impl Logger {
+    /// Generates a logger that writes eclipses in the given format
+    #[must_use]
+    pub fn new(format: LogFormat) -> Self {
+        Self {
+            format,
+            ..Default::default()
+        }
+    }
+
+    /// Adds records to the file at `path`, for structured formats
+    fn add_records(&self, path: std::path::PathBuf, records: Vec<EclipseRecord>) {
+        if let Ok(mut hash_map) = self.records.write() {
+            if let Some(values) = hash_map.get_mut(path.as_path()) {
+                values.extend(records);
+            } else {
+                hash_map.insert(path.into(), records);
+            }
+        }
+    }
+
+    /// Gets the path of a file of eclipses seen from an observatory, for structured formats
+    fn get_path(
+        &self,
+        output_path_root: &std::path::Path,
+        observatory_name: &str,
+        name: &str,
+    ) -> std::path::PathBuf {
+        let mut path = output_path_root.to_owned();
+        path.push(observatory_name);
+        path.push(format!("{name}.{}", self.format.get_extension()));
+        path
+    }
+}
+
+/// Unit: hours
+///
+/// Definition: How precisely the times of contacts are found when searching for eclipses
+const TOLERANCE: Float = 1e-4;
+
+/// How many times larger (in the sky) one body must be than the other for it to be a transit or
+/// occultation rather than an eclipse
+const SIZE_RATIO: Float = 2.0;
+
+/// The type of an eclipse, by how the near body covers the far body
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EclipseKind {
+    /// The near body entirely covers the far body, and they are a similar size
+    Total,
+    /// The near body is entirely inside the far body, leaving a ring, and they are a similar size
+    Annular,
+    /// The near body covers part of the far body, or the far body is partly in the umbra of the
+    /// near body
+    Partial,
+    /// The far body is only in the penumbra of the near body, and is not hidden by it
+    Penumbral,
+    /// A much smaller near body passes in front of the far body, e.g. a planet crossing a star
+    Transit,
+    /// A much larger near body passes in front of the far body, e.g. a moon hiding a star
+    Occultation,
+}
+
+impl EclipseKind {
+    /// Classifies an eclipse between a near and far body with the given angular radii (in
+    /// radians) that are `separation` radians apart in the sky, returns `None` if they don't
+    /// overlap
+    #[must_use]
+    pub fn classify(near_radius: Float, far_radius: Float, separation: Float) -> Option<Self> {
+        if separation >= near_radius + far_radius {
+            None
+        } else if near_radius >= far_radius * SIZE_RATIO {
+            Some(Self::Occultation)
+        } else if near_radius * SIZE_RATIO <= far_radius {
+            Some(Self::Transit)
+        } else if separation <= near_radius - far_radius {
+            Some(Self::Total)
+        } else if separation <= far_radius - near_radius {
+            Some(Self::Annular)
+        } else {
+            Some(Self::Partial)
+        }
+    }
+
+    /// Classifies a body in the shadow of another with the given umbral magnitude, i.e. the
+    /// fraction of its diameter inside the umbra
+    #[must_use]
+    pub fn classify_shadow(umbral_magnitude: Float) -> Self {
+        if umbral_magnitude >= 1.0 {
+            Self::Total
+        } else if umbral_magnitude > 0.0 {
+            Self::Partial
+        } else {
+            Self::Penumbral
+        }
+    }
+}
+
+impl Display for EclipseKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Total => write!(f, "total eclipse"),
+            Self::Annular => write!(f, "annular eclipse"),
+            Self::Partial => write!(f, "partial eclipse"),
+            Self::Penumbral => write!(f, "penumbral eclipse"),
+            Self::Transit => write!(f, "transit"),
+            Self::Occultation => write!(f, "occultation"),
+        }
+    }
+}
+
+/// How a near body covers a far body in the sky at some time
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Occlusion {
+    /// Type of the eclipse
+    pub kind: EclipseKind,
+    /// Fraction of the far body that is covered, above 1 if the near body is larger than needed to
+    /// cover it
+    pub magnitude: Float,
+    /// Unit: radians
+    ///
+    /// Definition: Angular radius of the near body
+    pub near_angular_radius: Float,
+    /// Unit: radians
+    ///
+    /// Definition: Angular radius of the far body
+    pub far_angular_radius: Float,
+    /// Unit: radians
+    ///
+    /// Definition: Angle between the centres of the bodies
+    pub separation: Float,
+}
+
+impl Occlusion {
+    /// Gets how a near `(body, location)` covers a far `(body, location)`, if at all
+    #[must_use]
+    pub fn new(
+        (near, near_location): (&crate::body::Arc, Spherical<Float>),
+        (far, far_location): (&crate::body::Arc, Spherical<Float>),
+    ) -> Option<Self> {
+        let near_angular_radius = near.read().ok()?.get_angular_radius(near_location.radius);
+        let far_angular_radius = far.read().ok()?.get_angular_radius(far_location.radius);
+        let separation = near_location.angle_to(&far_location);
+
+        Some(Self {
+            kind: EclipseKind::classify(near_angular_radius, far_angular_radius, separation)?,
+            magnitude: CollisionGrid::check_collision(
+                &(near_angular_radius, near_location),
+                &(far_angular_radius, far_location),
+            )?,
+            near_angular_radius,
+            far_angular_radius,
+            separation,
+        })
+    }
+}
+
+impl Display for Occlusion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} with magnitude {:.2}, angular radii {:.6} and {:.6}, separation {:.6}",
+            self.kind,
+            self.magnitude,
+            self.near_angular_radius,
+            self.far_angular_radius,
+            self.separation
+        )
+    }
+}
+
+/// An eclipse found by [`search_eclipses`]
+#[derive(Clone, Debug)]
+pub struct Eclipse {
+    /// Body in front, or casting the shadow
+    pub near: crate::body::Arc,
+    /// Body being hidden, or in the shadow
+    pub far: crate::body::Arc,
+    /// If the far body passes through the shadow of the near body (a lunar eclipse), rather than
+    /// behind it in the sky. The contacts are then the penumbral, partial umbral, and total
+    /// umbral stages.
+    pub shadow: bool,
+    /// Type of the eclipse at its greatest
+    pub kind: EclipseKind,
+    /// How the near body covers the far body in the sky at the greatest eclipse, `None` for lunar
+    /// eclipses
+    pub greatest: Option<Occlusion>,
+    /// When the eclipse starts, ends, and is greatest
+    pub event: Event,
+}
+
+/// Searches for eclipses seen from `observatory` between the sample `times`, including eclipses
+/// that are too short to be seen in any sample, and finds the times of their contacts. Lunar
+/// eclipses are included if the body in the shadow is visible at the greatest eclipse.
+#[must_use]
+pub fn search_eclipses(
+    observatory: &Observatory,
+    times: &[Float],
+    light_time: bool,
+) -> Vec<Eclipse> {
+    let observe = |time| observatory.observe_with_light_time(time, light_time);
+    let samples: Vec<Vec<LocalObservation>> = times.iter().map(|time| observe(*time)).collect();
+
+    // Every body seen at least once
+    let mut bodies: Vec<crate::body::Arc> = Vec::new();
+    for (body, _) in samples.iter().flatten() {
+        if !bodies.iter().any(|b| std::sync::Arc::ptr_eq(b, body)) {
+            bodies.push(body.clone());
+        }
+    }
+
+    let mut results = Vec::new();
+    for (i, a) in bodies.iter().enumerate() {
+        for b in &bodies[i + 1..] {
+            let Some((near, far)) = get_candidate(a, b, &samples) else {
+                continue;
+            };
+            let locate = |time| {
+                let observations = observe(time);
+                let find = |body: &crate::body::Arc| {
+                    observations
+                        .iter()
+                        .find(|(b, _)| std::sync::Arc::ptr_eq(b, body))
+                        .map(|(_, location)| *location)
+                };
+                Some(((near, find(near)?), (far, find(far)?)))
+            };
+            let events = event::search(times, TOLERANCE, |time| {
+                let (near, far) = locate(time)?;
+                get_overlap(near, far)
+            });
+            results.extend(events.into_iter().filter_map(|event| {
+                let (near, far) = locate(event.maximum)?;
+                let greatest = Occlusion::new(near, far)?;
+                Some(Eclipse {
+                    near: near.0.clone(),
+                    far: far.0.clone(),
+                    shadow: false,
+                    kind: greatest.kind,
+                    greatest: Some(greatest),
+                    event,
+                })
+            }));
+        }
+    }
+
+    let root = Body::get_root(observatory.get_body());
+    for (near, far, event, kind) in shadow::search_shadow_eclipses(&root, times, TOLERANCE) {
+        let visible = observe(event.maximum)
+            .iter()
+            .any(|(body, _)| std::sync::Arc::ptr_eq(body, &far));
+        if visible {
+            results.push(Eclipse {
+                near,
+                far,
+                shadow: true,
+                kind,
+                greatest: None,
+                event,
+            });
+        }
+    }
+
+    results
+}
+
+/// Gets the angle between a near and far `(body, location)` in the sky, where the thresholds are
+/// the angles where the bodies first touch, and where one is entirely inside the other. Returns
+/// `None` if the near body is not in front of the far body.
+fn get_overlap(
+    (near, near_location): (&crate::body::Arc, Spherical<Float>),
+    (far, far_location): (&crate::body::Arc, Spherical<Float>),
+) -> Option<Overlap> {
+    if near_location.radius >= far_location.radius {
+        return None;
+    }
+    let near_radius = near.read().ok()?.get_angular_radius(near_location.radius);
+    let far_radius = far.read().ok()?.get_angular_radius(far_location.radius);
+
+    Some(Overlap {
+        separation: near_location.angle_to(&far_location),
+        thresholds: vec![near_radius + far_radius, (near_radius - far_radius).abs()],
+    })
+}
+
+/// Returns the pair as `(near, far)` if they come close enough in the `samples` for an eclipse to
+/// happen between samples, i.e. the gap between them is smaller than the largest change in the
+/// gap from one sample to the next
+fn get_candidate<'a>(
+    a: &'a crate::body::Arc,
+    b: &'a crate::body::Arc,
+    samples: &[Vec<LocalObservation>],
+) -> Option<(&'a crate::body::Arc, &'a crate::body::Arc)> {
+    let find = |observations: &[LocalObservation], body: &crate::body::Arc| {
+        observations
+            .iter()
+            .find(|(b, _)| std::sync::Arc::ptr_eq(b, body))
+            .map(|(_, location)| *location)
+    };
+
+    // The gap between the bodies, along with which is closer
+    let gaps: Vec<Option<(Float, bool)>> = samples
+        .iter()
+        .map(|observations| {
+            let a_location = find(observations, a)?;
+            let b_location = find(observations, b)?;
+            let a_is_near = a_location.radius < b_location.radius;
+            let (near, far) = if a_is_near {
+                ((a, a_location), (b, b_location))
+            } else {
+                ((b, b_location), (a, a_location))
+            };
+            let overlap = get_overlap(near, far)?;
+            Some((overlap.separation - overlap.thresholds[0], a_is_near))
+        })
+        .collect();
+
+    let largest_change = gaps
+        .windows(2)
+        .filter_map(|pair| Some((pair[1]?.0 - pair[0]?.0).abs()))
+        .fold(0.0, Float::max);
+    let (_, a_is_near) = gaps
+        .into_iter()
+        .flatten()
+        .filter(|(gap, _)| *gap < largest_change || *gap < 0.0)
+        .min_by(|x, y| x.0.total_cmp(&y.0))?;
+
+    Some(if a_is_near { (a, b) } else { (b, a) })
+}
+
+/// Formats an optional time for the eclipse log
+fn format_time(time: Option<Float>) -> String {
+    time.map_or_else(|| "-".to_string(), |time| format!("{time:.4}"))
 }
 
 /// Gets a list of eclipses that have been observed at this time
 fn get_eclipses_on_frame(
     observations: &[(crate::body::Arc, Spherical<Float>)],
-    time: &str,
+    time: Float,
 ) -> Vec<String> {
-    // TODO: Handle lunar eclipses
     // Create an object to speed up searches similar to a hashgrid or oct-tree
     let grid = CollisionGrid::new(observations);
 
@@ -37,17 +389,70 @@ fn get_eclipses_on_frame(
                 .map(|p| p.get_name())
                 .unwrap_or("Poisoned Body".into());
 
-        for (other, magnitude) in grid.collisions(p) {
+        for (other, occlusion) in grid.collisions(p) {
             // For each body this body has eclipsed, get the name of the far body
             let other_name = other
                 .read()
                 .map(|b| b.get_name())
                 .unwrap_or("Poisoned Body".into());
 
-            results.push(format!("Time={time}, There was an eclipse between {name} and {other_name} with magnitude {magnitude:.2}"));
+            results.push(format!(
+                "Time={time}, There was an eclipse between {name} and {other_name}: {occlusion}"
+            ));
         }
     }
 
+    // Lunar eclipses, where a body passes through the shadow of another
+    for eclipse in shadow::get_shadow_eclipses(observations, time) {
+        let name = eclipse
+            .target
+            .read()
+            .map(|b| b.get_name())
+            .unwrap_or("Poisoned Body".into());
+        let occluder_name = eclipse
+            .occluder
+            .read()
+            .map(|b| b.get_name())
+            .unwrap_or("Poisoned Body".into());
+
+        results.push(format!("Time={time}, There was a lunar eclipse of {name} by the shadow of {occluder_name}: {} with penumbral magnitude {:.2} and umbral magnitude {:.2}", EclipseKind::classify_shadow(eclipse.umbral_magnitude), eclipse.penumbral_magnitude, eclipse.umbral_magnitude));
+    }
+
+    results
+}
+
+/// Gets records of eclipses that have been observed from the named observatory at this time
+fn get_records_on_frame(
+    observations: &[LocalObservation],
+    time: Float,
+    observatory: &str,
+) -> Vec<EclipseRecord> {
+    let grid = CollisionGrid::new(observations);
+
+    let mut results = Vec::new();
+    for p in observations {
+        for (other, occlusion) in grid.collisions(p) {
+            results.push(EclipseRecord {
+                magnitude: Some(occlusion.magnitude),
+                separation: Some(occlusion.separation),
+                ..EclipseRecord::new(time, observatory, &p.0, &other, occlusion.kind)
+            });
+        }
+    }
+
+    for eclipse in shadow::get_shadow_eclipses(observations, time) {
+        let kind = EclipseKind::classify_shadow(eclipse.umbral_magnitude);
+        results.push(EclipseRecord {
+            lunar: true,
+            magnitude: Some(if kind == EclipseKind::Penumbral {
+                eclipse.penumbral_magnitude
+            } else {
+                eclipse.umbral_magnitude
+            }),
+            ..EclipseRecord::new(time, observatory, &eclipse.occluder, &eclipse.target, kind)
+        });
+    }
+
     results
 }
 
@@ -60,7 +465,20 @@ impl Output for Logger {
         time: i128,
         output_path_root: &std::path::Path,
     ) -> Result<(), std::io::Error> {
-        let log = get_eclipses_on_frame(observations, &time.to_string());
+        if self.format != LogFormat::Text {
+            // Precision loss is inevitable since we are going from an integer to a (compile-time) variable length float
+            #[allow(clippy::cast_precision_loss)]
+            let records = get_records_on_frame(observations, time as Float, observatory_name);
+            self.add_records(
+                self.get_path(output_path_root, observatory_name, "eclipses"),
+                records,
+            );
+            return Ok(());
+        }
+
+        // Precision loss is inevitable since we are going from an integer to a (compile-time) variable length float
+        #[allow(clippy::cast_precision_loss)]
+        let log = get_eclipses_on_frame(observations, time as Float);
         let path = super::super::to_default_path(
             output_path_root,
             observatory_name,
@@ -78,6 +496,110 @@ impl Output for Logger {
         Ok(())
     }
 
+    fn write_events(
+        &self,
+        observatory: &Observatory,
+        times: &[Float],
+        light_time: bool,
+        output_path_root: &std::path::Path,
+    ) -> Result<(), std::io::Error> {
+        let eclipses = search_eclipses(observatory, times, light_time);
+        if self.format != LogFormat::Text {
+            let name = observatory.get_name();
+            let records = eclipses
+                .into_iter()
+                .map(|eclipse| {
+                    let event = &eclipse.event;
+                    // The contacts of lunar eclipses are the first and last times the body is in
+                    // the penumbra and fully in the umbra, with the partial umbral stage kept
+                    // separately
+                    let (inner, outer, umbral) = if eclipse.shadow {
+                        (
+                            event.contacts.get(2),
+                            event.contacts.first(),
+                            event.contacts.get(1),
+                        )
+                    } else {
+                        (event.contacts.get(1), event.contacts.first(), None)
+                    };
+                    EclipseRecord {
+                        lunar: eclipse.shadow,
+                        magnitude: eclipse.greatest.map(|greatest| greatest.magnitude),
+                        separation: eclipse.greatest.map(|greatest| greatest.separation),
+                        first_contact: outer.and_then(|contact| contact.0),
+                        second_contact: inner.and_then(|contact| contact.0),
+                        third_contact: inner.and_then(|contact| contact.1),
+                        fourth_contact: outer.and_then(|contact| contact.1),
+                        umbral_first_contact: umbral.and_then(|contact| contact.0),
+                        umbral_last_contact: umbral.and_then(|contact| contact.1),
+                        ..EclipseRecord::new(
+                            event.maximum,
+                            &name,
+                            &eclipse.near,
+                            &eclipse.far,
+                            eclipse.kind,
+                        )
+                    }
+                })
+                .collect();
+            self.add_records(
+                self.get_path(output_path_root, &name, "eclipse-events"),
+                records,
+            );
+            return Ok(());
+        }
+
+        let log = eclipses
+            .into_iter()
+            .map(|eclipse| {
+                let name = |body: &crate::body::Arc| {
+                    body.read()
+                        .map(|b| b.get_name())
+                        .unwrap_or("Poisoned Body".into())
+                };
+                let (near, far) = (name(&eclipse.near), name(&eclipse.far));
+                let event = &eclipse.event;
+                if eclipse.shadow {
+                    let penumbral = event.contacts.first().copied().unwrap_or_default();
+                    let umbral = event.contacts.get(1).copied().unwrap_or_default();
+                    let total = event.contacts.get(2).copied().unwrap_or_default();
+                    format!(
+                        "Lunar eclipse of {far} by the shadow of {near}: {}, P1={}, U1={}, U2={}, Maximum={:.4}, U3={}, U4={}, P4={}",
+                        eclipse.kind,
+                        format_time(penumbral.0),
+                        format_time(umbral.0),
+                        format_time(total.0),
+                        event.maximum,
+                        format_time(total.1),
+                        format_time(umbral.1),
+                        format_time(penumbral.1),
+                    )
+                } else {
+                    format!(
+                        "Eclipse of {far} by {near}: {}, C1={}, C2={}, Maximum={:.4}, C3={}, C4={}",
+                        eclipse
+                            .greatest
+                            .map_or_else(|| eclipse.kind.to_string(), |greatest| greatest.to_string()),
+                        format_time(event.get_first_contact()),
+                        format_time(event.get_second_contact()),
+                        event.maximum,
+                        format_time(event.get_third_contact()),
+                        format_time(event.get_fourth_contact()),
+                    )
+                }
+            })
+            .collect();
+
+        let mut path = output_path_root.to_owned();
+        path.push(observatory.get_name());
+        path.push("eclipses.txt");
+        if let Ok(mut hash_map) = self.eclipse_log.write() {
+            hash_map.insert(path.into(), log);
+        }
+
+        Ok(())
+    }
+
     fn flush(&self) -> Result<(), std::io::Error> {
         if let Ok(hash_map) = self.eclipse_log.read() {
             for (path, data) in hash_map.iter() {
@@ -92,6 +614,31 @@ impl Output for Logger {
                 file.write_all(data.join("\n").as_bytes())?;
             }
         }
+
+        if let Ok(mut hash_map) = self.records.write() {
+            for (path, records) in hash_map.iter_mut() {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+
+                // Observations are made in parallel, so may be out of order
+                records.sort_by(|a, b| a.time.total_cmp(&b.time));
+                let mut lines = Vec::with_capacity(records.len() + 1);
+                if self.format == LogFormat::Csv {
+                    lines.push(EclipseRecord::CSV_HEADER.to_string());
+                }
+                for record in records.iter() {
+                    lines.push(match self.format {
+                        LogFormat::Csv => record.to_csv(),
+                        _ => record.to_json().map_err(std::io::Error::other)?,
+                    });
+                }
+
+                // One file for the whole run, so overwrite any old file
+                let mut file = std::fs::File::create(path)?;
+                file.write_all((lines.join("\n") + "\n").as_bytes())?;
+            }
+        }
         Ok(())
     }
 }
@@ -101,7 +648,7 @@ mod tests {
     use super::*;
     use coordinates::prelude::Spherical;
 
-    use crate::{body::Body, dynamic::fixed::Fixed};
+    use crate::{body::Body, consts::float, dynamic::fixed::Fixed};
 
     #[test]
     fn eclipse_is_logged_in_correct_format() {
@@ -124,14 +671,269 @@ mod tests {
             })
             .collect();
 
-        let log = get_eclipses_on_frame(&observations, &time.to_string());
+        let log = get_eclipses_on_frame(&observations, time);
 
         assert_eq!(
             log[0],
             format!(
-                "Time={time}, There was an eclipse between {} and {} with magnitude {:.2}",
-                "0-0", "", 1.0
+                "Time={time}, There was an eclipse between {} and {}: total eclipse with magnitude {:.2}, angular radii {:.6} and {:.6}, separation {:.6}",
+                "0-0", "", 1.0, 0.01, 0.01, 0.0
             )
         );
     }
+
+    #[test]
+    fn classification() {
+        // Similar sizes
+        assert_eq!(
+            EclipseKind::classify(0.01, 0.01, 0.0),
+            Some(EclipseKind::Total)
+        );
+        assert_eq!(
+            EclipseKind::classify(0.011, 0.01, 0.0005),
+            Some(EclipseKind::Total)
+        );
+        assert_eq!(
+            EclipseKind::classify(0.009, 0.01, 0.0005),
+            Some(EclipseKind::Annular)
+        );
+        assert_eq!(
+            EclipseKind::classify(0.009, 0.01, 0.005),
+            Some(EclipseKind::Partial)
+        );
+        assert_eq!(EclipseKind::classify(0.009, 0.01, 0.02), None);
+
+        // Very different sizes, even when only partly overlapping
+        assert_eq!(
+            EclipseKind::classify(0.001, 0.01, 0.0),
+            Some(EclipseKind::Transit)
+        );
+        assert_eq!(
+            EclipseKind::classify(0.001, 0.01, 0.0105),
+            Some(EclipseKind::Transit)
+        );
+        assert_eq!(
+            EclipseKind::classify(0.01, 0.001, 0.0),
+            Some(EclipseKind::Occultation)
+        );
+
+        assert_eq!(EclipseKind::classify_shadow(1.2), EclipseKind::Total);
+        assert_eq!(EclipseKind::classify_shadow(0.3), EclipseKind::Partial);
+        assert_eq!(EclipseKind::classify_shadow(-0.3), EclipseKind::Penumbral);
+
+        // Structured form
+        let occlusion = Occlusion {
+            kind: EclipseKind::Transit,
+            magnitude: 0.1,
+            near_angular_radius: 0.001,
+            far_angular_radius: 0.01,
+            separation: 0.0,
+        };
+        let json = serde_json::to_string(&occlusion).unwrap();
+        assert!(json.contains(r#""kind":"transit""#));
+        assert!(json.contains("nearAngularRadius"));
+    }
+
+    #[test]
+    fn lunar_eclipse_is_logged() {
+        let sun = Body::new(None, Fixed::new([0.0, 0.0, 0.0].into()));
+        let earth = Body::new(Some(sun.clone()), Fixed::new([500.0, 0.0, 0.0].into()));
+        // Directly behind the earth
+        let moon = Body::new(Some(earth.clone()), Fixed::new([1.28, 0.0, 0.0].into()));
+        sun.write().unwrap().radius = Some(2.32);
+        earth.write().unwrap().radius = Some(0.0213);
+        moon.write().unwrap().radius = Some(0.0058);
+        Body::hydrate_all(&sun, &None);
+
+        let observations: Vec<_> = earth
+            .read()
+            .unwrap()
+            .get_observations_from_here(0.0)
+            .into_iter()
+            .map(|(b, loc)| (b, Spherical::from(loc)))
+            .collect();
+
+        let eclipses = shadow::get_shadow_eclipses(&observations, 0.0);
+        assert_eq!(eclipses.len(), 1);
+        assert!(std::sync::Arc::ptr_eq(&eclipses[0].target, &moon));
+        assert!(std::sync::Arc::ptr_eq(&eclipses[0].occluder, &earth));
+        // A total eclipse
+        assert!(eclipses[0].umbral_magnitude > 1.0);
+
+        let log = get_eclipses_on_frame(&observations, 0.0);
+        assert!(log
+            .iter()
+            .any(|line| line
+                .starts_with("Time=0, There was a lunar eclipse of 0-0 by the shadow of 0")));
+
+        // Moving the moon a quarter of an orbit takes it out of the shadow
+        moon.write().unwrap().dynamic = Box::new(Fixed::new([0.0, 1.28, 0.0].into()));
+        assert!(shadow::get_shadow_eclipses(&observations, 0.0).is_empty());
+    }
+
+    #[test]
+    fn short_eclipse_between_samples() {
+        use crate::{
+            body::observatory::{Observatory, Pointing},
+            dynamic::circular::Circular,
+        };
+        use coordinates::prelude::{ThreeDimensionalConsts, Vector3};
+
+        let root = Body::new(None, Fixed::new([0.0, 0.0, 0.0].into()));
+        let far = Body::new(Some(root.clone()), Fixed::new([100.0, 0.0, 0.0].into()));
+        // Passes in front of the far body once a day, taking about 7 minutes
+        let near = Body::new(
+            Some(root.clone()),
+            Circular::new_with_period(10.0, 0.0, 0.0, 1.0, 24.0),
+        );
+        far.write().unwrap().radius = Some(1.0);
+        near.write().unwrap().radius = Some(0.05);
+        Body::hydrate_all(&root, &None);
+        let observatory = Observatory::new_free(
+            // Out of the plane of the orbit, so the root never hides the near body
+            Vector3::FORWARD * 0.01,
+            Pointing::LookAt(far.clone()),
+            root,
+            Err(vec![]),
+            vec![],
+        );
+
+        let times: Vec<Float> = (0..24).map(|t| t as Float).collect();
+        let eclipses = search_eclipses(&observatory, &times, false);
+        assert_eq!(eclipses.len(), 1);
+        let eclipse = &eclipses[0];
+        assert_eq!(eclipse.kind, EclipseKind::Transit);
+        assert!(std::sync::Arc::ptr_eq(&eclipse.near, &near));
+        assert!(std::sync::Arc::ptr_eq(&eclipse.far, &far));
+
+        // Never eclipsed at a sample, so the logger can't see it
+        let event = &eclipse.event;
+        let (first, fourth) = (
+            event.get_first_contact().unwrap(),
+            event.get_fourth_contact().unwrap(),
+        );
+        assert!(first.ceil() > fourth);
+        assert!(first < event.maximum && event.maximum < fourth);
+        let duration = 0.015 * 2.0 / (float::TAU / 24.0);
+        assert_float_absolute_eq!(fourth - first, duration, 1e-3);
+
+        // The near body fits entirely inside the far body
+        let second = event.get_second_contact().unwrap();
+        let third = event.get_third_contact().unwrap();
+        assert!(first < second && third < fourth);
+        assert_float_absolute_eq!(third - second, duration / 3.0, 1e-3);
+    }
+
+    #[test]
+    fn structured_log() {
+        let sun = Body::new(None, Fixed::new([0.0, 0.0, 0.0].into()));
+        let earth = Body::new(Some(sun.clone()), Fixed::new([2.0, 0.0, 0.0].into()));
+        let _moon = Body::new(Some(earth.clone()), Fixed::new([-1.0, 0.0, 0.0].into()));
+        Body::hydrate_all(&sun, &None);
+        let observations: Vec<_> = earth
+            .read()
+            .unwrap()
+            .get_observations_from_here(0.0)
+            .into_iter()
+            .map(|(b, loc)| (b, Spherical::from(loc)))
+            .collect();
+
+        let root = crate::testing::get_temp_directory("structured-log");
+        for (format, extension) in [(LogFormat::JsonLines, "jsonl"), (LogFormat::Csv, "csv")] {
+            let logger = Logger::new(format);
+            // Out of order, as they would be when observing in parallel
+            for time in [3, 1, 2] {
+                logger
+                    .write_observations(&observations, &[], "observatory", time, &root)
+                    .unwrap();
+            }
+            logger.flush().unwrap();
+
+            // A single file for the observatory, in time order
+            let path = root.join(format!("observatory/eclipses.{extension}"));
+            let file = std::fs::read_to_string(&path).unwrap();
+            let mut lines: Vec<&str> = file.lines().collect();
+            if format == LogFormat::Csv {
+                assert_eq!(lines.remove(0), EclipseRecord::CSV_HEADER);
+                assert!(lines[0].starts_with("1,observatory,0-0,0-0,,,total,false,1,"));
+            } else {
+                let record: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+                assert_eq!(record["time"], 1.0);
+                assert_eq!(record["nearId"], serde_json::json!([0, 0]));
+                assert_eq!(record["kind"], "total");
+                assert_eq!(record["separation"], 0.0);
+            }
+            assert_eq!(lines.len(), 3);
+            assert!(lines[2].contains('3'));
+        }
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn structured_lunar_contacts() {
+        use crate::{
+            body::observatory::{Observatory, Pointing},
+            dynamic::circular::Circular,
+        };
+        use coordinates::prelude::{ThreeDimensionalConsts, Vector3};
+
+        let sun = Body::new(None, Fixed::new([0.0, 0.0, 0.0].into()));
+        let earth = Body::new(Some(sun.clone()), Fixed::new([500.0, 0.0, 0.0].into()));
+        // Passes through the shadow of the earth once a day
+        let moon = Body::new(
+            Some(earth.clone()),
+            Circular::new_with_period(1.28, 0.0, 0.0, 1.0, 24.0),
+        );
+        sun.write().unwrap().radius = Some(2.32);
+        earth.write().unwrap().radius = Some(0.0213);
+        moon.write().unwrap().radius = Some(0.0058);
+        Body::hydrate_all(&sun, &None);
+        let observatory = Observatory::new_free(
+            // Out of the plane of the orbit, so the earth never hides the moon
+            Vector3::FORWARD * 0.1,
+            Pointing::LookAt(moon),
+            earth,
+            Err(vec![]),
+            vec![],
+        );
+
+        let root = crate::testing::get_temp_directory("structured-lunar-contacts");
+        let logger = Logger::new(LogFormat::JsonLines);
+        let times: Vec<Float> = (0..24).map(|t| t as Float).collect();
+        logger
+            .write_events(&observatory, &times, false, &root)
+            .unwrap();
+        logger.flush().unwrap();
+
+        let path = root.join(format!("{}/eclipse-events.jsonl", observatory.get_name()));
+        let file = std::fs::read_to_string(path).unwrap();
+        // The earth also passes through the shadow of the moon
+        let records: Vec<serde_json::Value> = file
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        let record = records
+            .iter()
+            .find(|record| record["farId"] == serde_json::json!([0, 0]))
+            .unwrap();
+        assert_eq!(record["lunar"], true);
+        assert_eq!(record["kind"], "total");
+        // P1 < U1 < U2 < U3 < U4 < P4, as in the text log
+        let contacts: Vec<Float> = [
+            "firstContact",
+            "umbralFirstContact",
+            "secondContact",
+            "thirdContact",
+            "umbralLastContact",
+            "fourthContact",
+        ]
+        .iter()
+        .map(|key| record[key].as_f64().unwrap() as Float)
+        .collect();
+        assert!(contacts.windows(2).all(|pair| pair[0] < pair[1]));
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
 }
@@ -0,0 +1,249 @@
+use serde::Serialize;
+
+use super::EclipseKind;
+use crate::{
+    body::{observatory::to_name, Arc},
+    Float,
+};
+
+/// How the [`super::Logger`] writes eclipses
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Sentences in a `-eclipses.txt` file for each time step, and an `eclipses.txt` file of
+    /// searched eclipses for each observatory
+    #[default]
+    Text,
+    /// One [`EclipseRecord`] JSON object per line, in an `eclipses.jsonl` and
+    /// `eclipse-events.jsonl` file for each observatory
+    JsonLines,
+    /// One [`EclipseRecord`] per row after a header, in an `eclipses.csv` and `eclipse-events.csv`
+    /// file for each observatory
+    Csv,
+}
+
+impl LogFormat {
+    /// Gets the extension of files in this format
+    #[must_use]
+    pub fn get_extension(self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::JsonLines => "jsonl",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// A single eclipse seen from an observatory, in a form that is easy to parse
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EclipseRecord {
+    /// Unit: hours
+    ///
+    /// Definition: Time of the observation, or of the greatest eclipse for searched eclipses
+    pub time: Float,
+    /// Name of the observatory that saw the eclipse
+    pub observatory: String,
+    /// ID of the body in front, or casting the shadow
+    pub near_id: Vec<usize>,
+    /// Name of the body in front, or casting the shadow
+    pub near_name: String,
+    /// ID of the body being hidden, or in the shadow
+    pub far_id: Vec<usize>,
+    /// Name of the body being hidden, or in the shadow
+    pub far_name: String,
+    /// Type of the eclipse
+    pub kind: EclipseKind,
+    /// If the far body is in the shadow of the near body, rather than behind it in the sky
+    pub lunar: bool,
+    /// Fraction of the far body that is covered. For lunar eclipses this is the fraction of the
+    /// diameter in the umbra, or in the penumbra for penumbral eclipses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub magnitude: Option<Float>,
+    /// Unit: radians
+    ///
+    /// Definition: Angle between the centres of the bodies in the sky, `None` for lunar eclipses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub separation: Option<Float>,
+    /// Unit: hours
+    ///
+    /// Definition: Contact times of searched eclipses, see [`crate::event::Event`]. These are
+    /// the penumbral and total umbral contacts for lunar eclipses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_contact: Option<Float>,
+    /// See [`Self::first_contact`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub second_contact: Option<Float>,
+    /// See [`Self::first_contact`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub third_contact: Option<Float>,
+    /// See [`Self::first_contact`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fourth_contact: Option<Float>,
+    /// Unit: hours
+    ///
+    /// Definition: First time a searched lunar eclipse touches the umbra (U1), `None` for other
+    /// eclipses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub umbral_first_contact: Option<Float>,
+    /// Unit: hours
+    ///
+    /// Definition: Last time a searched lunar eclipse touches the umbra (U4), `None` for other
+    /// eclipses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub umbral_last_contact: Option<Float>,
+}
+
+impl EclipseRecord {
+    /// Generates a record of an eclipse of the `far` body by the `near` body, without a
+    /// magnitude, separation, or contacts
+    #[must_use]
+    pub fn new(time: Float, observatory: &str, near: &Arc, far: &Arc, kind: EclipseKind) -> Self {
+        let describe = |body: &Arc| {
+            body.read().map_or_else(
+                |_| (Vec::new(), "Poisoned Body".to_string()),
+                |body| (body.get_id(), body.get_name().to_string()),
+            )
+        };
+        let (near_id, near_name) = describe(near);
+        let (far_id, far_name) = describe(far);
+
+        Self {
+            time,
+            observatory: observatory.to_string(),
+            near_id,
+            near_name,
+            far_id,
+            far_name,
+            kind,
+            lunar: false,
+            magnitude: None,
+            separation: None,
+            first_contact: None,
+            second_contact: None,
+            third_contact: None,
+            fourth_contact: None,
+            umbral_first_contact: None,
+            umbral_last_contact: None,
+        }
+    }
+
+    /// Header row for [`Self::to_csv`]
+    pub const CSV_HEADER: &'static str = "time,observatory,nearId,nearName,farId,farName,kind,lunar,magnitude,separation,firstContact,secondContact,thirdContact,fourthContact,umbralFirstContact,umbralLastContact";
+
+    /// Formats the record as a line of JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the record cannot be serialized, e.g. a value is not finite
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Formats the record as a CSV row with the columns in [`Self::CSV_HEADER`], IDs are dash
+    /// separated and missing values are empty
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let optional = |value: Option<Float>| value.map(|v| v.to_string()).unwrap_or_default();
+        [
+            self.time.to_string(),
+            to_csv_field(&self.observatory),
+            to_name(&self.near_id),
+            to_csv_field(&self.near_name),
+            to_name(&self.far_id),
+            to_csv_field(&self.far_name),
+            // Same names as in JSON
+            serde_json::to_value(self.kind)
+                .ok()
+                .and_then(|kind| kind.as_str().map(str::to_string))
+                .unwrap_or_default(),
+            self.lunar.to_string(),
+            optional(self.magnitude),
+            optional(self.separation),
+            optional(self.first_contact),
+            optional(self.second_contact),
+            optional(self.third_contact),
+            optional(self.fourth_contact),
+            optional(self.umbral_first_contact),
+            optional(self.umbral_last_contact),
+        ]
+        .join(",")
+    }
+}
+
+/// Quotes a CSV field if it contains characters that would break the row
+fn to_csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EclipseRecord, LogFormat};
+    use crate::output::logger::eclipse::EclipseKind;
+
+    fn get_record() -> EclipseRecord {
+        EclipseRecord {
+            time: 3.0,
+            observatory: "Greenwich, London".to_string(),
+            near_id: vec![2, 0],
+            near_name: "Moon".to_string(),
+            far_id: vec![],
+            far_name: "Sun".to_string(),
+            kind: EclipseKind::Total,
+            lunar: false,
+            magnitude: Some(1.05),
+            separation: Some(0.0001),
+            first_contact: None,
+            second_contact: None,
+            third_contact: None,
+            fourth_contact: None,
+            umbral_first_contact: None,
+            umbral_last_contact: None,
+        }
+    }
+
+    #[test]
+    fn json_lines() {
+        let json = get_record().to_json().unwrap();
+        assert!(!json.contains('\n'));
+        assert!(json.contains(r#""nearId":[2,0]"#));
+        assert!(json.contains(r#""kind":"total""#));
+        assert!(!json.contains("firstContact"));
+        assert!(!json.contains("umbralFirstContact"));
+    }
+
+    #[test]
+    fn csv() {
+        let columns = EclipseRecord::CSV_HEADER.split(',').count();
+        let row = get_record().to_csv();
+        assert_eq!(
+            row,
+            r#"3,"Greenwich, London",2-0,Moon,,Sun,total,false,1.05,0.0001,,,,,,"#
+        );
+        // The quoted comma is not a separator
+        assert_eq!(row.split(',').count(), columns + 1);
+        assert_eq!(LogFormat::Csv.get_extension(), "csv");
+    }
+
+    #[test]
+    fn lunar_contacts() {
+        // P1, U1, U2, U3, U4, P4
+        let record = EclipseRecord {
+            lunar: true,
+            separation: None,
+            first_contact: Some(1.0),
+            second_contact: Some(3.0),
+            third_contact: Some(4.0),
+            fourth_contact: Some(6.0),
+            umbral_first_contact: Some(2.0),
+            umbral_last_contact: Some(5.0),
+            ..get_record()
+        };
+        let json = record.to_json().unwrap();
+        assert!(json.contains(r#""umbralFirstContact":2.0,"umbralLastContact":5.0"#));
+        assert!(record.to_csv().ends_with(",true,1.05,,1,3,4,6,2,5"));
+    }
+}
@@ -0,0 +1,273 @@
+use coordinates::{
+    prelude::Vector3,
+    traits::{Dot, Magnitude},
+};
+
+use super::EclipseKind;
+use crate::{
+    body::{Arc, Body},
+    event::{self, Event, Overlap},
+    Float, LocalObservation,
+};
+
+/// A body passing through the shadow another body casts away from the root of the tree, e.g. the
+/// moon passing through the shadow of the earth during a lunar eclipse
+pub struct ShadowEclipse {
+    /// Body casting the shadow
+    pub occluder: Arc,
+    /// Body in the shadow
+    pub target: Arc,
+    /// Fraction of the diameter of the target inside the penumbra, eclipses start when this is
+    /// above zero
+    pub penumbral_magnitude: Float,
+    /// Fraction of the diameter of the target inside the umbra, negative if the target is only in
+    /// the penumbra
+    pub umbral_magnitude: Float,
+}
+
+/// Gets every observed body that is inside the shadow of another body. The root of the tree is
+/// the light source, and bodies (including the root) without a radius are ignored.
+pub fn get_shadow_eclipses(observations: &[LocalObservation], time: Float) -> Vec<ShadowEclipse> {
+    let Some((first, _)) = observations.first() else {
+        return Vec::new();
+    };
+    let root = Body::get_root(first);
+    let Ok(root) = root.read() else {
+        return Vec::new();
+    };
+    let Some(star_radius) = root.radius else {
+        return Vec::new();
+    };
+
+    // Every body that can cast or receive a shadow, relative to the root
+    let bodies: Vec<(Arc, Vector3<Float>, Float)> = root
+        .get_inertial_observations_from_here(time, false, false)
+        .into_iter()
+        .filter_map(|(body, location)| {
+            let radius = body.read().ok()?.radius?;
+            Some((body, location, radius))
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    for (target, target_location, target_radius) in bodies.iter().filter(|(body, _, _)| {
+        observations
+            .iter()
+            .any(|(observed, _)| std::sync::Arc::ptr_eq(body, observed))
+    }) {
+        for (occluder, occluder_location, occluder_radius) in &bodies {
+            if std::sync::Arc::ptr_eq(target, occluder) {
+                continue;
+            }
+
+            let Some((penumbral_magnitude, umbral_magnitude)) = get_magnitudes(
+                star_radius,
+                (*occluder_location, *occluder_radius),
+                (*target_location, *target_radius),
+            ) else {
+                continue;
+            };
+            results.push(ShadowEclipse {
+                occluder: occluder.clone(),
+                target: target.clone(),
+                penumbral_magnitude,
+                umbral_magnitude,
+            });
+        }
+    }
+
+    results
+}
+
+/// Gets the penumbral and umbral magnitudes of a `(location, radius)` target in the shadow of an
+/// occluder cast by a star at the origin, if the target is in the penumbra
+fn get_magnitudes(
+    star_radius: Float,
+    occluder: (Vector3<Float>, Float),
+    target: (Vector3<Float>, Float),
+) -> Option<(Float, Float)> {
+    let overlap = get_overlap(star_radius, occluder, target)?;
+    let to_magnitude = |threshold: Float| (threshold - overlap.separation) / (2.0 * target.1);
+    let penumbral_magnitude = to_magnitude(overlap.thresholds[0]);
+    (penumbral_magnitude > 0.0).then(|| (penumbral_magnitude, to_magnitude(overlap.thresholds[1])))
+}
+
+/// Gets how far a `(location, radius)` target is from the axis of the shadow of an occluder cast
+/// by a star at the origin. The thresholds are where the target touches the outside of the
+/// penumbra, touches the outside of the umbra, and is entirely inside the umbra.
+fn get_overlap(
+    star_radius: Float,
+    (occluder_location, occluder_radius): (Vector3<Float>, Float),
+    (target_location, target_radius): (Vector3<Float>, Float),
+) -> Option<Overlap> {
+    let star_distance = occluder_location.magnitude();
+    if star_distance == 0.0 || target_radius <= 0.0 {
+        return None;
+    }
+
+    // Distance behind the occluder along the axis of the shadow, and from the axis
+    let axis = occluder_location / star_distance;
+    let behind = target_location - occluder_location;
+    let depth = behind.dot(&axis);
+    if depth <= 0.0 {
+        return None;
+    }
+    let from_axis = (behind - axis * depth).magnitude();
+
+    // Similar triangles between the edges of the star and the occluder. The umbra radius goes
+    // negative past its tip, where the shadow becomes an antumbra.
+    let penumbra = occluder_radius + depth * (star_radius + occluder_radius) / star_distance;
+    let umbra = occluder_radius - depth * (star_radius - occluder_radius) / star_distance;
+
+    Some(Overlap {
+        separation: from_axis,
+        thresholds: vec![
+            penumbra + target_radius,
+            umbra + target_radius,
+            umbra - target_radius,
+        ],
+    })
+}
+
+/// Searches for bodies passing through the shadows of other bodies between the sample `times`,
+/// see [`crate::event::search`]. The contacts of each event are the penumbral, partial umbral,
+/// and total umbral stages.
+pub fn search_shadow_eclipses(
+    root: &Arc,
+    times: &[Float],
+    tolerance: Float,
+) -> Vec<(Arc, Arc, Event, EclipseKind)> {
+    let Ok(root) = root.read() else {
+        return Vec::new();
+    };
+    let Some(star_radius) = root.radius else {
+        return Vec::new();
+    };
+    let bodies: Vec<(Arc, Float)> = root
+        .get_inertial_observations_from_here(0.0, false, false)
+        .into_iter()
+        .filter_map(|(body, _)| {
+            let radius = body.read().ok()?.radius?;
+            Some((body, radius))
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    for (target, target_radius) in &bodies {
+        for (occluder, occluder_radius) in &bodies {
+            if std::sync::Arc::ptr_eq(target, occluder) {
+                continue;
+            }
+
+            let locate = |time| {
+                Some((
+                    (
+                        occluder.read().ok()?.get_absolute_offset(time),
+                        *occluder_radius,
+                    ),
+                    (
+                        target.read().ok()?.get_absolute_offset(time),
+                        *target_radius,
+                    ),
+                ))
+            };
+            let events = event::search(times, tolerance, |time| {
+                let (occluder, target) = locate(time)?;
+                get_overlap(star_radius, occluder, target)
+            });
+            results.extend(events.into_iter().filter_map(|event| {
+                let (occluder_location, target_location) = locate(event.maximum)?;
+                let (_, umbral_magnitude) =
+                    get_magnitudes(star_radius, occluder_location, target_location)?;
+                Some((
+                    occluder.clone(),
+                    target.clone(),
+                    event,
+                    EclipseKind::classify_shadow(umbral_magnitude),
+                ))
+            }));
+        }
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use coordinates::prelude::{ThreeDimensionalConsts, Vector3};
+
+    use super::get_magnitudes;
+
+    #[test]
+    fn magnitudes() {
+        let earth = (Vector3::RIGHT * 100.0, 1.0);
+
+        // Centred in the shadow
+        let (penumbral, umbral) =
+            get_magnitudes(10.0, earth, (Vector3::RIGHT * 101.0, 0.25)).unwrap();
+        assert_float_absolute_eq!(umbral, (0.91 + 0.25) / 0.5, 1e-5);
+        assert_float_absolute_eq!(penumbral, (1.11 + 0.25) / 0.5, 1e-5);
+
+        // Just inside the penumbra
+        let edge = Vector3::from((101.0, 1.11 + 0.2, 0.0));
+        let (penumbral, umbral) = get_magnitudes(10.0, earth, (edge, 0.25)).unwrap();
+        assert_float_absolute_eq!(penumbral, 0.1, 1e-5);
+        assert!(umbral < 0.0);
+
+        // Outside the shadow, and between the star and the occluder
+        assert!(get_magnitudes(10.0, earth, (Vector3::from((101.0, 2.0, 0.0)), 0.25)).is_none());
+        assert!(get_magnitudes(10.0, earth, (Vector3::RIGHT * 99.0, 0.25)).is_none());
+    }
+
+    #[test]
+    fn search_finds_all_stages() {
+        use crate::{
+            body::Body,
+            dynamic::{circular::Circular, fixed::Fixed},
+            Float,
+        };
+
+        let sun = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        let earth = Body::new(Some(sun.clone()), Fixed::new(Vector3::RIGHT * 500.0));
+        let moon = Body::new(
+            Some(earth.clone()),
+            Circular::new_with_period(1.28, 0.0, 0.0, 0.0, 24.0),
+        );
+        sun.write().unwrap().radius = Some(2.32);
+        earth.write().unwrap().radius = Some(0.0213);
+        moon.write().unwrap().radius = Some(0.0058);
+        Body::hydrate_all(&sun, &None);
+
+        let times: Vec<Float> = (-12..12).map(|t| t as Float).collect();
+        let eclipses = super::search_shadow_eclipses(&sun, &times, 1e-5);
+        // Half an orbit away, the shadow of the moon falls on the earth at the start
+        assert_eq!(eclipses.len(), 2);
+        let (occluder, _, event, kind) = eclipses
+            .iter()
+            .find(|(_, target, _, _)| std::sync::Arc::ptr_eq(target, &moon))
+            .unwrap();
+        assert!(std::sync::Arc::ptr_eq(occluder, &earth));
+        assert_eq!(*kind, super::EclipseKind::Total);
+
+        // Greatest when the moon is directly behind the earth, with every stage in order
+        assert_float_absolute_eq!(
+            moon.read().unwrap().get_absolute_offset(event.maximum).z,
+            0.0,
+            1e-4
+        );
+        let mut contacts: Vec<Float> = event
+            .contacts
+            .iter()
+            .map(|contact| contact.0.unwrap())
+            .collect();
+        contacts.push(event.maximum);
+        contacts.extend(
+            event
+                .contacts
+                .iter()
+                .rev()
+                .map(|contact| contact.1.unwrap()),
+        );
+        assert!(contacts.windows(2).all(|w| w[0] < w[1]));
+    }
+}
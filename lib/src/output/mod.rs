@@ -5,7 +5,7 @@ use std::{
 
 use dyn_clone::DynClone;
 
-use crate::LocalObservation;
+use crate::{body::observatory::Observatory, LocalObservation};
 
 /// An output for SVG files
 pub mod svg;
@@ -28,6 +28,45 @@ pub trait Output: DynClone + Debug + Sync {
         output_path_root: &Path,
     ) -> Result<(), std::io::Error>;
 
+    /// Same as [`Self::write_observations`], but with access to the observatory the observations
+    /// were made from, e.g. to draw its [horizon](Observatory::get_horizon). Defaults to
+    /// [`Self::write_observations`] with the name of the observatory.
+    ///
+    /// # Errors
+    /// See [`Self::write_observations`]
+    fn write_observatory_observations(
+        &self,
+        observatory: &Observatory,
+        observations: &[LocalObservation],
+        constellations: &[crate::constellation::Line],
+        time: i128,
+        output_path_root: &Path,
+    ) -> Result<(), std::io::Error> {
+        self.write_observations(
+            observations,
+            constellations,
+            &observatory.get_name(),
+            time,
+            output_path_root,
+        )
+    }
+
+    /// Writes events that happen at any time during a run of observations made from `observatory`
+    /// at the sample `times`, e.g. eclipses found with [`crate::event::search`]. Called once for
+    /// each observatory after every observation is written. Does nothing by default.
+    ///
+    /// # Errors
+    /// See [`Self::write_observations`]
+    fn write_events(
+        &self,
+        _observatory: &Observatory,
+        _times: &[crate::Float],
+        _light_time: bool,
+        _output_path_root: &Path,
+    ) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
     /// # Errors
     /// implementations may panif if there is an error in the filesystem e.g. the user is missing
     /// permissions, a directory in the path is a file.
@@ -1,6 +1,11 @@
 use std::{fmt::Debug, path::Path};
 
-use crate::{consts::float, projection::Projection, Float, LocalObservation};
+use crate::{
+    body::{horizon::HorizonMask, observatory::Observatory},
+    consts::float,
+    projection::Projection,
+    Float, LocalObservation,
+};
 
 use coordinates::three_dimensional::Spherical;
 
@@ -8,7 +13,7 @@ use super::Output;
 use coordinates::prelude::{Polar, Vector2};
 use svg::{
     self,
-    node::element::{Circle, Line, Rectangle, Style, Text},
+    node::element::{Circle, Line, Polygon, Rectangle, Style, Text},
     Document, Node,
 };
 
@@ -29,6 +34,50 @@ impl<T: Projection> Svg<T> {
         time: &str,
         observations: &[LocalObservation],
         constellations: &[(Spherical<Float>, Spherical<Float>)],
+    ) -> svg::Document {
+        self.consume_observation_with_horizon(time, observations, constellations, None)
+    }
+
+    /// Converts observations to a SVG document, drawing the silhouette of the `horizon` in front
+    /// of everything else if there is one
+    pub fn consume_observation_with_horizon(
+        &self,
+        time: &str,
+        observations: &[LocalObservation],
+        constellations: &[(Spherical<Float>, Spherical<Float>)],
+        horizon: Option<&HorizonMask>,
+    ) -> svg::Document {
+        self.draw(time, observations, constellations, horizon, |_| 1.0)
+    }
+
+    /// Converts observations made from `observatory` to a SVG document, drawing its horizon in
+    /// front of everything else and dimming each body by the
+    /// [transmission](Observatory::get_transmission) of its atmosphere
+    pub fn consume_observatory_observation(
+        &self,
+        time: &str,
+        observatory: &Observatory,
+        observations: &[LocalObservation],
+        constellations: &[(Spherical<Float>, Spherical<Float>)],
+    ) -> svg::Document {
+        self.draw(
+            time,
+            observations,
+            constellations,
+            observatory.get_horizon(),
+            |location| observatory.get_transmission(location),
+        )
+    }
+
+    /// Draws the observations, with the opacity of each body given by `transmission` of its
+    /// location
+    fn draw(
+        &self,
+        time: &str,
+        observations: &[LocalObservation],
+        constellations: &[(Spherical<Float>, Spherical<Float>)],
+        horizon: Option<&HorizonMask>,
+        transmission: impl Fn(&Spherical<Float>) -> Float,
     ) -> svg::Document {
         // TODO: remove some magic values (like "0.005", "-0.95", etc.)
 
@@ -106,14 +155,14 @@ impl<T: Projection> Svg<T> {
         }
 
         // Display the bodies on top of everything else
-        for (body, projected_location, distance) in observations
+        for (body, projected_location, location) in observations
             .iter()
             // Map from world space to "screen space" (we still require some uniform
             // transformations to map to a true screen space)
             .filter_map(|(body, loc)| {
                 self.0
                     .project_with_state(loc)
-                    .map(|projection| (body, projection, loc.radius))
+                    .map(|projection| (body, projection, loc))
             })
         {
             let circle = Circle::new()
@@ -121,7 +170,9 @@ impl<T: Projection> Svg<T> {
                     "r",
                     body.read()
                         // Set radius to a small but still visible value if angular diameter is too small
-                        .map(|b| (b.get_angular_radius(distance) * float::FRAC_1_PI).max(0.005))
+                        .map(|b| {
+                            (b.get_angular_radius(location.radius) * float::FRAC_1_PI).max(0.005)
+                        })
                         // or we don't have the information for it
                         .unwrap_or(0.005),
                 )
@@ -129,6 +180,8 @@ impl<T: Projection> Svg<T> {
                 .set("cy", projected_location.y)
                 // TODO: set color based on body type or name? (Will likely require user defined settings)
                 .set("fill", "#FFF")
+                // Light absorbed by the atmosphere, e.g. near the horizon, makes the body dimmer
+                .set("fill-opacity", transmission(location).clamp(0.0, 1.0))
                 .set(
                     "class",
                     body.read()
@@ -139,8 +192,42 @@ impl<T: Projection> Svg<T> {
             result.append(circle);
         }
 
+        // Terrain hides anything behind it
+        if let Some(horizon) = horizon {
+            result.append(self.get_silhouette(horizon));
+        }
+
         return result;
     }
+
+    /// Gets the shape between the mathematical horizon and the outline of the `horizon`
+    fn get_silhouette(&self, horizon: &HorizonMask) -> Polygon {
+        /// Number of points along the outline
+        const SAMPLES: u16 = 360;
+
+        let azimuths = (0..=SAMPLES).map(|i| Float::from(i) / Float::from(SAMPLES) * float::TAU);
+        let outline = azimuths.clone().map(|azimuth| Spherical {
+            radius: 1.0,
+            // Clamp to the mathematical horizon, since projections can't show below it
+            polar_angle: float::FRAC_PI_2 - horizon.get_altitude(azimuth).max(0.0),
+            azimuthal_angle: azimuth,
+        });
+        let base = azimuths.rev().map(|azimuth| Spherical {
+            radius: 1.0,
+            polar_angle: float::FRAC_PI_2,
+            azimuthal_angle: azimuth,
+        });
+
+        let points: Vec<String> = outline
+            .chain(base)
+            .filter_map(|location| self.0.project_with_state(&location))
+            .map(|point| format!("{},{}", point.x, point.y))
+            .collect();
+
+        Polygon::new()
+            .set("points", points.join(" "))
+            .set("class", "horizon")
+    }
 }
 
 impl<T> Output for Svg<T>
@@ -150,6 +237,31 @@ where
     T: Debug,
     T: Sync,
 {
+    /// Outputs [`Self::consume_observatory_observation`] to a given file.
+    fn write_observatory_observations(
+        &self,
+        observatory: &Observatory,
+        observations: &[LocalObservation],
+        constellations: &[crate::constellation::Line],
+        time: i128,
+        output_path_root: &Path,
+    ) -> Result<(), std::io::Error> {
+        let path = super::to_default_path(output_path_root, &observatory.get_name(), time, ".svg");
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        svg::save(
+            path,
+            &self.consume_observatory_observation(
+                &format!("{time:010}"),
+                observatory,
+                observations,
+                constellations,
+            ),
+        )
+    }
+
     /// Outputs [`Self::consume_observation`] to a given file.
     fn write_observations(
         &self,
@@ -170,3 +282,97 @@ where
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use coordinates::prelude::{Spherical, ThreeDimensionalConsts, Vector3};
+
+    use super::Svg;
+    use crate::{
+        body::{
+            atmosphere::Atmosphere, horizon::HorizonMask, observatory::Observatory,
+            rotating::Rotating, Body,
+        },
+        consts::float,
+        dynamic::fixed::Fixed,
+        projection::StatelessOrthographic,
+        Float,
+    };
+
+    /// Gets the `fill-opacity` of every circle in the `document`
+    fn get_opacities(document: &str) -> Vec<Float> {
+        document
+            .split(r#"fill-opacity=""#)
+            .skip(1)
+            .filter_map(|rest| rest.split('"').next()?.parse().ok())
+            .collect()
+    }
+
+    #[test]
+    fn horizon_silhouette() {
+        let svg = Svg::new(StatelessOrthographic());
+        assert!(!svg
+            .consume_observation("0", &[], &[])
+            .to_string()
+            .contains(r#"class="horizon""#));
+
+        let horizon = HorizonMask::new(vec![(0.0, 0.0), (1.0, 0.5), (2.0, 0.0)]).unwrap();
+        let document = svg
+            .consume_observation_with_horizon("0", &[], &[], Some(&horizon))
+            .to_string();
+        assert!(document.contains(r#"class="horizon""#));
+    }
+
+    #[test]
+    fn atmosphere_dims_bodies_near_horizon() {
+        let planet = Body::new(None, Fixed::new(Vector3::ORIGIN));
+        planet.write().unwrap().rotation = Some(Rotating::new(24.0, Spherical::UP));
+        // One body overhead, and one three degrees above the horizon
+        let _ = Body::new(
+            Some(planet.clone()),
+            Fixed::new(Vector3::from((10.0, 0.0, 0.0))),
+        );
+        let low = (3.0 as Float).to_radians().tan() * 10.0;
+        let _ = Body::new(
+            Some(planet.clone()),
+            Fixed::new(Vector3::from((low, 10.0, 0.0))),
+        );
+        Body::hydrate_all(&planet, &None);
+        let observatory = Observatory::new(
+            Spherical::new(1.0, float::FRAC_PI_2, 0.0),
+            planet.clone(),
+            Err(vec![]),
+            vec![],
+        );
+        let svg = Svg::new(StatelessOrthographic());
+
+        // Without air every body is drawn fully bright
+        let observations = observatory.observe(0.0);
+        assert_eq!(observations.len(), 2);
+        let document = svg
+            .consume_observatory_observation("0", &observatory, &observations, &[])
+            .to_string();
+        assert_eq!(get_opacities(&document), vec![1.0, 1.0]);
+
+        planet
+            .write()
+            .unwrap()
+            .set_atmosphere(Some(Atmosphere::default()));
+        let observations = observatory.observe(0.0);
+        let document = svg
+            .consume_observatory_observation("0", &observatory, &observations, &[])
+            .to_string();
+        let opacities = get_opacities(&document);
+        assert_eq!(opacities.len(), 2);
+        let overhead = observations
+            .iter()
+            .position(|(_, location)| location.polar_angle < 0.1)
+            .unwrap();
+        assert!(opacities[overhead] > 0.5);
+        assert!(opacities[1 - overhead] < opacities[overhead] / 2.0);
+
+        // Observations without an observatory are not dimmed
+        let document = svg.consume_observation("0", &observations, &[]).to_string();
+        assert_eq!(get_opacities(&document), vec![1.0, 1.0]);
+    }
+}
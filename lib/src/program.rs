@@ -11,6 +11,7 @@ use crate::{
         observatory::{to_observatory, Observatory, WeakObservatory},
         Arc,
     },
+    dynamic::n_body::NBody,
     output::Output,
     Float,
 };
@@ -27,13 +28,23 @@ pub struct Program {
     #[builder(setter(each(name = "add_observatory")))]
     observatories: Vec<Observatory>,
     /// List of outputs to use.
-    #[builder(setter(each(name = "add_output")))]
+    #[builder(default, setter(each(name = "add_output")))]
     #[serde(skip)]
     outputs: Vec<Box<dyn crate::output::Output>>,
     /// Location where output files will be stored, typically under a subdirectory for which
     /// observatory made that observation.
     #[builder(default)]
     output_file_root: PathBuf,
+    /// If set, observations are made of an N-body integration over the observation time range,
+    /// see [`NBody::integrate`]. The bodies themselves are left unchanged.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n_body: Option<NBody>,
+    /// If set, every observatory corrects its observations for light-travel time, see
+    /// [`Observatory::observe_with_light_time`]
+    #[builder(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    light_time: bool,
 }
 
 impl Program {
@@ -66,15 +77,43 @@ impl Program {
             return;
         }
 
+        // Integrate a copy of the tree for this run, so the bodies and observatories are left
+        // as they were. The root is kept since bodies only hold weak references to their parents.
+        let (_root, observatories) = match &self.n_body {
+            Some(n_body) => {
+                let root =
+                    n_body.integrate(&self._root_body, start_time as Float, end_time as Float);
+                let observatories = self
+                    .observatories
+                    .iter()
+                    .map(|observatory| observatory.with_root(&root))
+                    .collect();
+                (root, observatories)
+            }
+            None => (self._root_body.clone(), self.observatories.clone()),
+        };
+
         let times: Vec<_> = (start_time..end_time)
             .step_by(step_size.unwrap_or(1))
             .collect();
 
         let _: Vec<()> = times
             .par_iter()
-            .map(|time| self.make_observation(*time))
+            .map(|time| self.make_observation(&observatories, *time))
             .collect();
 
+        let times: Vec<Float> = times.into_iter().map(|time| time as Float).collect();
+        for observatory in &observatories {
+            let light_time = self.light_time || observatory.has_light_time();
+            for output in &self.outputs {
+                if let Err(e) =
+                    output.write_events(observatory, &times, light_time, &self.output_file_root)
+                {
+                    warn!("{e}");
+                }
+            }
+        }
+
         for output in &self.outputs {
             match output.flush() {
                 Ok(()) => (),
@@ -83,22 +122,26 @@ impl Program {
         }
     }
 
-    /// Makes a single observation to help with parallel computation
-    fn make_observation(&self, time: i128) {
+    /// Makes a single observation from each of the `observatories` to help with parallel
+    /// computation
+    fn make_observation(&self, observatories: &[Observatory], time: i128) {
         info!("Calculating observations for t={time}");
-        for observatory in &self.observatories {
+        for observatory in observatories {
             let path = self
                 .output_file_root
                 .join(format!("{}/{time:010}", observatory.get_name()));
-            let observations = observatory.observe(time as Float);
+            let observations = observatory.observe_with_light_time(
+                time as Float,
+                self.light_time || observatory.has_light_time(),
+            );
             let constellations = observatory.add_constelatations(&observations);
             for output in &self.outputs {
                 // Write the observations to file, recovering on errors
                 // HACK: Should remove this match statement and return an error on writing
-                match output.write_observations(
+                match output.write_observatory_observations(
+                    observatory,
                     &observations,
                     &constellations,
-                    &observatory.get_name(),
                     time,
                     &self.output_file_root,
                 ) {
@@ -128,9 +171,39 @@ impl Program {
         self.output_file_root = output.into();
     }
 
+    /// Set the N-body integration settings, or [`None`] to use the dynamics as they are
+    pub fn set_n_body(&mut self, n_body: Option<NBody>) {
+        self.n_body = n_body;
+    }
+
+    /// Set whether every observatory corrects its observations for light-travel time, otherwise
+    /// each observatory uses its own setting
+    pub fn set_light_time(&mut self, light_time: bool) {
+        self.light_time = light_time;
+    }
+
+    /// Gets whether every observatory corrects its observations for light-travel time, see
+    /// [`Self::set_light_time`]
+    #[must_use]
+    pub fn has_light_time(&self) -> bool {
+        self.light_time
+    }
+
     pub fn add_output(&mut self, output_method: Box<dyn Output>) {
         self.outputs.push(output_method);
     }
+
+    /// Gets the root of the tree of bodies
+    #[must_use]
+    pub fn get_root_body(&self) -> &Arc {
+        &self._root_body
+    }
+
+    /// Gets the observatories that observations are made from
+    #[must_use]
+    pub fn get_observatories(&self) -> &[Observatory] {
+        &self.observatories
+    }
 }
 
 /// Intermediate type to allow deserializing programs and maintaining validity of the data
@@ -143,6 +216,12 @@ struct DeserializedProgram {
     observatories: Vec<WeakObservatory>,
     /// The output path
     output_file_root: PathBuf,
+    /// Optional N-body integration settings
+    #[serde(default)]
+    n_body: Option<NBody>,
+    /// Correct all observations for light-travel time
+    #[serde(default)]
+    light_time: bool,
 }
 
 impl From<DeserializedProgram> for Program {
@@ -160,6 +239,8 @@ impl From<DeserializedProgram> for Program {
             observatories,
             output_file_root: value.output_file_root,
             outputs: Vec::new(),
+            n_body: value.n_body,
+            light_time: value.light_time,
         }
     }
 }
@@ -182,6 +263,8 @@ mod tests {
             root_body: root.clone(),
             observatories,
             output_file_root: PathBuf::default(),
+            n_body: None,
+            light_time: false,
         };
 
         let program: Program = dp.into();
@@ -189,6 +272,24 @@ mod tests {
         assert_eq!(6, program.observatories.len());
     }
 
+    #[test]
+    fn n_body_leaves_bodies_unchanged() {
+        let program = include_str!("../../assets/solar-system.program.json");
+        let mut program: Program = serde_json::from_str(program).unwrap();
+        let root = crate::testing::get_temp_directory("n-body-program");
+        program.set_output_path(&root);
+        let json = serde_json::to_string(&program).unwrap();
+        program.set_n_body(Some(NBody::new(
+            1.0,
+            crate::dynamic::n_body::Integrator::default(),
+        )));
+
+        program.make_observations(0, 2, None);
+        program.set_n_body(None);
+        assert!(serde_json::to_string(&program).unwrap() == json);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
     #[test]
     fn deserialize() {
         let program = include_str!("../../assets/solar-system.program.json");
@@ -2,10 +2,14 @@
 
 use astrograph::{
     body::{
+        atmosphere::Atmosphere,
         observatory::{self, Observatory, WeakObservatory},
         rotating::Rotating,
     },
-    dynamic::{fixed::Fixed, keplerian::Keplerian},
+    dynamic::{
+        barycentric::Barycentric, circular::Circular, fixed::Fixed, hyperbolic::Hyperbolic,
+        keplerian::Keplerian, tabulated::Tabulated,
+    },
     generator::{artifexian::ArtifexianBuilder, Generator},
     program::ProgramBuilder,
     Float,
@@ -116,11 +120,16 @@ struct Body {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
     rotation: Option<Rotating>,
-    // Getting some parameters ready for a next version
-    // /// Mass of the body in jupiter masses
-    //mass: Float,
+    /// Mass of the body in jupiter masses
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    mass: Option<crate::Float>,
     #[serde(skip_serializing_if = "Option::is_none")]
     radius: Option<crate::Float>,
+    /// Air above the surface of the body
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    atmosphere: Option<Atmosphere>,
     //color: [u8,h8,u8],
     #[serde(skip_serializing_if = "Option::is_none")]
     name: Option<String>,
@@ -140,11 +149,17 @@ impl From<Body> for astrograph::body::Body {
                     .map(|c| astrograph::body::Body::from(c).into())
                     .collect(),
             )
+            .mass(value.mass)
             .radius(value.radius)
+            .atmosphere(value.atmosphere)
             .rotation(value.rotation)
             .dynamic(match value.dynamic {
                 Dynamic::Fixed(f) => Box::new(f),
                 Dynamic::Keplerian(f) => Box::new(f),
+                Dynamic::Hyperbolic(f) => Box::new(f),
+                Dynamic::Circular(f) => Box::new(f),
+                Dynamic::Barycentric(f) => Box::new(f),
+                Dynamic::Tabulated(f) => Box::new(f),
             })
             .build()
         {
@@ -162,6 +177,14 @@ enum Dynamic {
     Fixed(Fixed),
     /// Keplerian bodies
     Keplerian(Keplerian),
+    /// Hyperbolic and parabolic bodies
+    Hyperbolic(Hyperbolic),
+    /// Circular bodies
+    Circular(Circular),
+    /// Bodies that orbit a shared centre of mass
+    Barycentric(Barycentric),
+    /// Bodies that follow a precomputed table of offsets
+    Tabulated(Tabulated),
 }
 
 #[cfg(test)]
@@ -176,7 +199,9 @@ mod test {
             children: vec![],
             dynamic: Dynamic::Fixed(Fixed::new(Vector3::<astrograph::Float>::ORIGIN)),
             rotation: None,
+            mass: None,
             radius: None,
+            atmosphere: None,
             name: None,
         };
 
@@ -1,6 +1,7 @@
 use astrograph::projection;
 
 use astrograph::{
+    body::observatory::Observatory,
     constellation::Line,
     output::{svg::Svg, Output},
 };
@@ -21,6 +22,15 @@ extern "C" {
     fn draw_observation(time: i128, observations: String);
 }
 
+impl Web {
+    /// Stores a SVG document of observations until the next flush
+    fn store_observations(&self, observations: svg::Document, time: i128) {
+        if let Ok(mut hash_map) = self.observations.write() {
+            hash_map.insert(time, observations);
+        }
+    }
+}
+
 impl Output for Web {
     fn write_observations(
         &self,
@@ -30,14 +40,31 @@ impl Output for Web {
         time: i128,
         _output_path_root: &std::path::Path,
     ) -> Result<(), std::io::Error> {
-        let observations =
+        self.store_observations(
             self.svg
-                .consume_observation(&format!("{time}"), observations, constellations);
-
-        if let Ok(mut hash_map) = self.observations.write() {
-            hash_map.insert(time, observations);
-        }
+                .consume_observation(&format!("{time}"), observations, constellations),
+            time,
+        );
+        Ok(())
+    }
 
+    fn write_observatory_observations(
+        &self,
+        observatory: &Observatory,
+        observations: &[astrograph::LocalObservation],
+        constellations: &[Line],
+        time: i128,
+        _output_path_root: &std::path::Path,
+    ) -> Result<(), std::io::Error> {
+        self.store_observations(
+            self.svg.consume_observatory_observation(
+                &format!("{time}"),
+                observatory,
+                observations,
+                constellations,
+            ),
+            time,
+        );
         Ok(())
     }
 